@@ -7,22 +7,28 @@ use euclid::{Point2D};
 
 use crate::math::*;
 
-use crate::sprite_sheet::{AnimationJSON, SheetJSON, Sprite, SpriteSheet};
+use crate::sprite_sheet::{AnimationJSON, SheetJSON, Sprite, SpriteSheet, SpriteMask, DrawStyle};
 use crate::frame::{Frame};
+use crate::asset::AssetReport;
+use crate::assets::{Assets, SpriteHandle};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Animation {
     framerate: u32,
     current: usize,
     rate: u32,
     bounding_box: Rect,
-    sprites: Vec<Sprite>,
+    /// frames, which are added to the assets when loaded
+    sprites: Vec<SpriteHandle>,
 }
 
 impl Animation {
     pub fn new(
         ani_json: &AnimationJSON, 
-        sheet_json: &SheetJSON) -> Self {
+        sheet_json: &SheetJSON,
+        sheet: &SpriteSheet,
+        assets: &mut Assets,
+        report: &mut AssetReport) -> Self {
         let mut animation = Animation {
             framerate: ani_json.framerate as u32,
             current: 0,
@@ -32,10 +38,10 @@ impl Animation {
         };
 
         for n in &ani_json.frames {
-            let s = sheet_json.frames.get(n).unwrap();
+            let s = report.frame(sheet_json, n);
             animation.sprites.push(
-                Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32));
-            animation.bounding_box.size = Size::new(s.frame.w as u32, s.frame.h as u32);
+                assets.add_sprite(Sprite::from_json(&s, sheet)));
+            animation.bounding_box.size = Size::new(s.sourceSize.w as u32, s.sourceSize.h as u32);
         }
 
         animation
@@ -45,6 +51,12 @@ impl Animation {
         self.bounding_box
     } 
 
+    /// returns the mask of the current frame, built when it was loaded
+    #[inline]
+    pub fn get_mask<'a>(&self, assets: &'a Assets) -> &'a SpriteMask {
+        assets.get_sprite(self.sprites[self.current]).get_mask()
+    }
+
     pub fn step(&mut self) {
         if self.rate == self.framerate {
             self.current = (self.current + 1) % self.sprites.len();
@@ -55,7 +67,18 @@ impl Animation {
         }
     }
 
-    pub fn render<'a>(&self, pos: Point2D<u32,u32>, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.sprites[self.current].render(pos.x, pos.y, sheet, frame);
+    /// move directly to the next frame, ignoring the framerate
+    pub fn next_frame(&mut self) {
+        self.current = (self.current + 1) % self.sprites.len();
+        self.rate = 0;
+    }
+
+    pub fn render<'a>(&self, assets: &Assets, pos: Point2D<u32,u32>, frame: &mut Frame<'a>) {
+        assets.get_sprite(self.sprites[self.current]).render(pos.x, pos.y, frame);
+    }
+
+    /// render the current frame, blended, tinted and faded as given by a style
+    pub fn render_with_style<'a>(&self, assets: &Assets, pos: Point2D<u32,u32>, style: &DrawStyle, frame: &mut Frame<'a>) {
+        assets.get_sprite(self.sprites[self.current]).render_with_style(pos.x, pos.y, style, frame);
     }
 }
\ No newline at end of file
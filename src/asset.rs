@@ -0,0 +1,514 @@
+//! Description:
+//!
+//! Errors found when loading assets, e.g. a missing file or a frame missing from the sprite sheet.
+//!
+//! Rather than stopping at the first problem, assets are loaded in a single pass that collects every
+//! problem into a report, which is shown at startup. Anything that could not be loaded is replaced
+//! by an empty placeholder, so that loading can carry on, but the game does not start if any problems
+//! were found.
+//!
+//! Assets are loaded from the asset root, which is the first directory found from:
+//!
+//!   1. the command line option `--assets <dir>`
+//!   2. the environment variable SPACE_INVADERS_ASSETS
+//!   3. `assets` alongside the executable
+//!   4. `space-invaders/assets` in the XDG data directory, e.g. `~/.local/share`
+//!   5. `./assets` in the working directory
+//!
+//! A root given on the command line, or in the environment, must exist, otherwise it is reported as a
+//! problem, rather than quietly loading assets from somewhere else.
+//!
+//! With the `embed-assets` feature the default sprite sheet, JSON, and sounds are built into the
+//! executable, and are used for any of them not found in the asset root.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::sprite_sheet::{AnimationJSON, SheetJSON, SpriteJSON};
+use crate::config::command_line_option;
+
+/// command line option used to set the asset root
+pub const ASSETS_ARG: &str = "--assets";
+/// environment variable used to set the asset root
+const ASSETS_ENV: &str = "SPACE_INVADERS_ASSETS";
+/// name of asset directory, alongside the executable or in the working directory
+const ASSETS_DIR: &str = "assets";
+/// asset directory within the XDG data directory
+const XDG_ASSETS_DIR: &str = "space-invaders/assets";
+
+lazy_static! {
+    /// directory assets are loaded from, or if one was given that does not exist, that directory
+    static ref ASSET_ROOT: Result<PathBuf, PathBuf> = find_asset_root();
+}
+
+/// a problem loading an asset
+#[derive(Debug)]
+pub enum AssetError {
+    /// file could not be opened or read
+    Read { path: String, error: io::Error },
+    /// file was read, but its contents are not valid
+    Invalid { path: String, error: String },
+    /// frame is not in the sprite sheet
+    MissingFrame { sheet: String, frame: String },
+    /// frame is in the sprite sheet, but lies outside of its image
+    FrameOutsideSheet { sheet: String, frame: String },
+    /// sprite sheet image is not the size given in the sprite sheet
+    SheetSizeMismatch { sheet: String, expected: (u32, u32), found: (u32, u32) },
+    /// animation is not in the animations file
+    MissingAnimation { animation: String },
+    /// sound file was read, but is not in a format that can be played
+    UndecodableSound { path: String },
+    /// asset root given on the command line, or in the environment, does not exist
+    MissingAssetRoot { path: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Read { path, error } =>
+                write!(f, "unable to read {}: {}", path, error),
+            AssetError::Invalid { path, error } =>
+                write!(f, "{} is not valid: {}", path, error),
+            AssetError::MissingFrame { sheet, frame } =>
+                write!(f, "frame \"{}\" is missing from sprite sheet {}", frame, sheet),
+            AssetError::FrameOutsideSheet { sheet, frame } =>
+                write!(f, "frame \"{}\" lies outside of sprite sheet {}", frame, sheet),
+            AssetError::SheetSizeMismatch { sheet, expected, found } =>
+                write!(
+                    f,
+                    "sprite sheet {} is {}x{}, but should be {}x{}",
+                    sheet, found.0, found.1, expected.0, expected.1),
+            AssetError::MissingAnimation { animation } =>
+                write!(f, "animation \"{}\" is missing", animation),
+            AssetError::UndecodableSound { path } =>
+                write!(f, "unable to decode sound {}", path),
+            AssetError::MissingAssetRoot { path } =>
+                write!(f, "asset directory {} does not exist", path),
+        }
+    }
+}
+
+impl std::error::Error for AssetError { }
+
+/// a directory the asset root might be
+#[derive(Debug, Clone, PartialEq)]
+struct RootCandidate {
+    dir: PathBuf,
+    /// given on the command line, or in the environment, so must exist
+    explicit: bool,
+}
+
+/// returns the directories the asset root might be, in the order they are tried
+///
+/// # Arguments
+///
+/// * `cli` - Directory given on the command line, if any
+/// * `env` - Directory given in the environment, if any
+/// * `exe` - Path of the executable, if known
+/// * `xdg_data_home` - XDG data directory, if set
+/// * `home` - Home directory, if set, the XDG data directory defaults to `.local/share` within it
+fn asset_root_candidates(
+    cli: Option<PathBuf>,
+    env: Option<PathBuf>,
+    exe: Option<PathBuf>,
+    xdg_data_home: Option<PathBuf>,
+    home: Option<PathBuf>) -> Vec<RootCandidate> {
+
+    let explicit = |dir| RootCandidate { dir, explicit: true };
+    let implicit = |dir| RootCandidate { dir, explicit: false };
+
+    let exe_dir = exe.and_then(|exe| exe.parent().map(|dir| dir.join(ASSETS_DIR)));
+    let xdg_dir = xdg_data_home
+        .or_else(|| home.map(|home| home.join(".local/share")))
+        .map(|dir| dir.join(XDG_ASSETS_DIR));
+
+    cli.map(explicit).into_iter()
+        .chain(env.map(explicit))
+        .chain(exe_dir.map(implicit))
+        .chain(xdg_dir.map(implicit))
+        .chain(std::iter::once(implicit(Path::new(".").join(ASSETS_DIR))))
+        .collect()
+}
+
+/// returns the first candidate that is a directory, or if one given explicitly is not, returns it as an error,
+/// if none are the last candidate is used, so that any problems are reported as missing assets within it
+///
+/// # Arguments
+///
+/// * `candidates` - Directories the asset root might be, in the order they are tried
+/// * `is_dir` - Returns true if a path is a directory
+fn choose_asset_root(candidates: Vec<RootCandidate>, is_dir: impl Fn(&Path) -> bool) -> Result<PathBuf, PathBuf> {
+    let mut last = PathBuf::from(ASSETS_DIR);
+    for candidate in candidates {
+        if is_dir(&candidate.dir) {
+            return Ok(candidate.dir);
+        }
+        if candidate.explicit {
+            return Err(candidate.dir);
+        }
+        last = candidate.dir;
+    }
+    Ok(last)
+}
+
+/// returns the asset root, the first directory found from the command line, environment, alongside
+/// the executable, XDG data directory, and finally the working directory
+fn find_asset_root() -> Result<PathBuf, PathBuf> {
+    let candidates = asset_root_candidates(
+        command_line_option(ASSETS_ARG).map(PathBuf::from),
+        std::env::var_os(ASSETS_ENV).map(PathBuf::from),
+        std::env::current_exe().ok(),
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+        std::env::var_os("HOME").map(PathBuf::from));
+
+    let root = choose_asset_root(candidates, |dir| dir.is_dir());
+    match &root {
+        Ok(dir) => log::info!("loading assets from {}", dir.display()),
+        Err(dir) => log::error!("asset directory {} does not exist", dir.display()),
+    }
+    root
+}
+
+/// add a problem to the report if the asset root was given, on the command line or in the environment, but
+/// does not exist
+///
+/// # Arguments
+///
+/// * `report` - Report of any assets that failed to load
+pub fn check_asset_root(report: &mut AssetReport) {
+    if let Err(dir) = &*ASSET_ROOT {
+        report.add(AssetError::MissingAssetRoot { path: dir.display().to_string() });
+    }
+}
+
+/// returns the path of an asset, within the asset root
+///
+/// # Arguments
+///
+/// * `name` - Asset's path, relative to the asset root
+pub fn asset_path<P: AsRef<Path>>(name: P) -> PathBuf {
+    match &*ASSET_ROOT {
+        Ok(dir) | Err(dir) => dir.join(name),
+    }
+}
+
+/// load an asset from the asset root, if it is not there the embedded asset is used, if there is one
+///
+/// # Arguments
+///
+/// * `name` - Asset's path, relative to the asset root
+pub fn load_asset<P: AsRef<Path>>(name: P) -> Result<Cow<'static, [u8]>, AssetError> {
+    let path = asset_path(&name);
+    match std::fs::read(&path) {
+        Ok(data) => Ok(Cow::Owned(data)),
+        Err(error) => match embedded_asset(name.as_ref()) {
+            Some(data) => {
+                log::debug!("{} not found, using embedded asset", path.display());
+                Ok(Cow::Borrowed(data))
+            },
+            None => Err(AssetError::Read { path: path.display().to_string(), error }),
+        },
+    }
+}
+
+/// returns an asset built into the executable, if there is one
+///
+/// # Arguments
+///
+/// * `name` - Asset's path, relative to the asset root
+#[cfg(feature = "embed-assets")]
+fn embedded_asset(name: &Path) -> Option<&'static [u8]> {
+    let name = name.strip_prefix(".").unwrap_or(name).to_str()?;
+    match name {
+        "sprite-sheet.png" => Some(include_bytes!("../assets/sprite-sheet.png")),
+        "sprite-sheet.json" => Some(include_bytes!("../assets/sprite-sheet.json")),
+        "sprite-animation.json" => Some(include_bytes!("../assets/sprite-animation.json")),
+        "sound-pack.json" => Some(include_bytes!("../assets/sound-pack.json")),
+        "sounds/player_shoot_16bit.wav" => Some(include_bytes!("../assets/sounds/player_shoot_16bit.wav")),
+        "sounds/player_explosion_16bit.wav" => Some(include_bytes!("../assets/sounds/player_explosion_16bit.wav")),
+        "sounds/alien_explosion_16bit.wav" => Some(include_bytes!("../assets/sounds/alien_explosion_16bit.wav")),
+        "sounds/ufo.wav" => Some(include_bytes!("../assets/sounds/ufo.wav")),
+        "sounds/ufo_highpitch 2.wav" => Some(include_bytes!("../assets/sounds/ufo_highpitch 2.wav")),
+        _ => None,
+    }
+}
+
+/// returns an asset built into the executable, there are none without the `embed-assets` feature
+#[cfg(not(feature = "embed-assets"))]
+fn embedded_asset(_name: &Path) -> Option<&'static [u8]> {
+    None
+}
+
+/// read a JSON asset
+///
+/// # Arguments
+///
+/// * `name` - JSON file to read, relative to the asset root
+pub fn read_json<T, P>(name: P) -> Result<T, AssetError>
+    where T: DeserializeOwned,
+          P: AsRef<Path> {
+    let data = load_asset(&name)?;
+    serde_json::from_slice(&data).map_err(|error| AssetError::Invalid { 
+        path: asset_path(&name).display().to_string(), 
+        error: error.to_string() })
+}
+
+/// problems found while loading assets, collected so that they can all be reported together
+///
+/// Errors stop the game from starting, warnings, e.g. a sound that cannot be played, do not.
+#[derive(Debug, Default)]
+pub struct AssetReport {
+    errors: Vec<AssetError>,
+    warnings: Vec<AssetError>,
+}
+
+impl AssetReport {
+    /// create an empty report
+    pub fn new() -> Self {
+        AssetReport::default()
+    }
+
+    /// returns true if no errors have been found, there may still be warnings
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// add a problem to the report, unless it has already been reported, e.g. an animation used twice
+    pub fn add(&mut self, error: AssetError) {
+        let message = error.to_string();
+        if !self.errors.iter().any(|e| e.to_string() == message) {
+            self.errors.push(error);
+        }
+    }
+
+    /// add a problem that does not stop the game from starting, unless it has already been reported
+    pub fn warn(&mut self, warning: AssetError) {
+        let message = warning.to_string();
+        if !self.warnings.iter().any(|w| w.to_string() == message) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// log any warnings found, for when the game starts anyway
+    pub fn log_warnings(&self) {
+        for warning in &self.warnings {
+            log::warn!("{}", warning);
+        }
+    }
+
+    /// returns a loaded asset, or if it failed to load adds the problem to the report and returns an empty
+    /// placeholder
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - Result of loading asset
+    pub fn check<T: Default>(&mut self, result: Result<T, AssetError>) -> T {
+        result.unwrap_or_else(|error| {
+            self.add(error);
+            T::default()
+        })
+    }
+
+    /// returns a frame from the sprite sheet, if it is missing, or outside of the sheet's image, adds the
+    /// problem to the report and returns an empty frame
+    ///
+    /// A sheet without any frames has failed to load, which is already reported, so its frames are not.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_json` - Sprite sheet
+    /// * `name` - Name of frame
+    pub fn frame(&mut self, sheet_json: &SheetJSON, name: &str) -> SpriteJSON {
+        let sheet = sheet_json.meta.image.clone();
+        match sheet_json.frames.get(name) {
+            Some(s) => {
+                let size = &sheet_json.meta.size;
+                let (w, h) = s.sheet_size();
+                if s.frame.x < 0 || s.frame.y < 0 || s.frame.x + w > size.w || s.frame.y + h > size.h {
+                    self.add(AssetError::FrameOutsideSheet { sheet, frame: name.to_string() });
+                    return SpriteJSON::default();
+                }
+                s.clone()
+            },
+            None => {
+                if !sheet_json.frames.is_empty() {
+                    self.add(AssetError::MissingFrame { sheet, frame: name.to_string() });
+                }
+                SpriteJSON::default()
+            },
+        }
+    }
+
+    /// returns an animation, if it is missing adds the problem to the report and returns an empty animation
+    ///
+    /// As with frames, missing animations are not reported if none were loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `anis_json` - Animations
+    /// * `name` - Name of animation
+    pub fn animation(&mut self, anis_json: &HashMap<String, AnimationJSON>, name: &str) -> AnimationJSON {
+        match anis_json.get(name) {
+            Some(animation) => animation.clone(),
+            None => {
+                if !anis_json.is_empty() {
+                    self.add(AssetError::MissingAnimation { animation: name.to_string() });
+                }
+                AnimationJSON::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for AssetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Unable to start, {} problem(s) found loading game assets:", self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "  - {}", error)?;
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f, "and {} warning(s):", self.warnings.len())?;
+            for warning in &self.warnings {
+                writeln!(f, "  - {}", warning)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sprite_sheet::{XYWHJSON, WHJSON, MetaJSON};
+
+    /// returns a frame at a position within the sheet
+    fn frame(x: i32, y: i32, w: i32, h: i32, rotated: bool) -> SpriteJSON {
+        SpriteJSON {
+            frame: XYWHJSON { x, y, w, h },
+            rotated,
+            ..SpriteJSON::default()
+        }
+    }
+
+    /// returns a 64x64 sheet with frames inside and outside of its image, the rotated frame only being outside
+    /// once rotated
+    fn sheet() -> SheetJSON {
+        let mut frames = HashMap::new();
+        frames.insert("inside".to_string(), frame(56, 0, 8, 8, false));
+        frames.insert("outside".to_string(), frame(60, 0, 8, 8, false));
+        frames.insert("negative".to_string(), frame(-1, 0, 8, 8, false));
+        frames.insert("rotated".to_string(), frame(60, 0, 4, 8, true));
+        SheetJSON {
+            frames,
+            meta: MetaJSON {
+                image: "sheet.png".to_string(),
+                size: WHJSON { w: 64, h: 64 },
+                ..MetaJSON::default()
+            },
+        }
+    }
+
+    #[test]
+    fn frame_inside_sheet() {
+        let mut report = AssetReport::new();
+        assert_eq!(report.frame(&sheet(), "inside").frame.x, 56);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn missing_frame_is_reported_once() {
+        let mut report = AssetReport::new();
+        let missing = report.frame(&sheet(), "missing");
+        report.frame(&sheet(), "missing");
+
+        assert_eq!(missing.frame.w, 0);
+        assert_eq!(report.errors.len(), 1);
+        assert!(matches!(&report.errors[0], AssetError::MissingFrame { sheet, frame }
+            if sheet == "sheet.png" && frame == "missing"));
+    }
+
+    #[test]
+    fn frame_outside_sheet_is_reported() {
+        let mut report = AssetReport::new();
+        for name in ["outside", "negative", "rotated"] {
+            assert_eq!(report.frame(&sheet(), name).frame.w, 0);
+        }
+
+        assert_eq!(report.errors.len(), 3);
+        assert!(report.errors.iter().all(|error| matches!(error, AssetError::FrameOutsideSheet { .. })));
+    }
+
+    #[test]
+    fn frames_of_unloaded_sheet_are_not_reported() {
+        let mut report = AssetReport::new();
+        report.frame(&SheetJSON::default(), "missing");
+        assert!(report.is_empty());
+    }
+
+    /// returns the candidates when every setting is given
+    fn all_candidates() -> Vec<RootCandidate> {
+        asset_root_candidates(
+            Some(PathBuf::from("/cli")),
+            Some(PathBuf::from("/env")),
+            Some(PathBuf::from("/opt/game/space-invaders")),
+            Some(PathBuf::from("/xdg")),
+            Some(PathBuf::from("/home/player")))
+    }
+
+    #[test]
+    fn asset_root_candidate_order() {
+        let dirs: Vec<(PathBuf, bool)> = all_candidates().into_iter().map(|c| (c.dir, c.explicit)).collect();
+        assert_eq!(dirs, vec![
+            (PathBuf::from("/cli"), true),
+            (PathBuf::from("/env"), true),
+            (PathBuf::from("/opt/game/assets"), false),
+            (PathBuf::from("/xdg/space-invaders/assets"), false),
+            (Path::new(".").join("assets"), false),
+        ]);
+
+        // XDG data directory defaults to within the home directory
+        let dirs: Vec<PathBuf> = asset_root_candidates(None, None, None, None, Some(PathBuf::from("/home/player")))
+            .into_iter()
+            .map(|c| c.dir)
+            .collect();
+        assert_eq!(dirs, vec![
+            PathBuf::from("/home/player/.local/share/space-invaders/assets"),
+            Path::new(".").join("assets"),
+        ]);
+    }
+
+    #[test]
+    fn asset_root_is_first_existing_directory() {
+        let exists = |dirs: &'static [&'static str]| move |dir: &Path| dirs.iter().any(|d| Path::new(d) == dir);
+
+        assert_eq!(choose_asset_root(all_candidates(), exists(&["/cli", "/env"])), Ok(PathBuf::from("/cli")));
+        assert_eq!(
+            choose_asset_root(all_candidates(), exists(&["/xdg/space-invaders/assets", "./assets"])),
+            Err(PathBuf::from("/cli")));
+
+        let implicit = asset_root_candidates(
+            None, None, Some(PathBuf::from("/opt/game/space-invaders")), Some(PathBuf::from("/xdg")), None);
+        assert_eq!(
+            choose_asset_root(implicit.clone(), exists(&["/xdg/space-invaders/assets", "./assets"])),
+            Ok(PathBuf::from("/xdg/space-invaders/assets")));
+        assert_eq!(choose_asset_root(implicit, exists(&[])), Ok(Path::new(".").join("assets")));
+    }
+
+    #[test]
+    fn missing_explicit_asset_root_is_an_error() {
+        // the environment is not used when the command line's directory is missing
+        let env_only = asset_root_candidates(None, Some(PathBuf::from("/env")), None, None, None);
+        assert_eq!(choose_asset_root(env_only, |_| false), Err(PathBuf::from("/env")));
+        assert_eq!(
+            choose_asset_root(all_candidates(), |dir| dir == Path::new("/env")),
+            Err(PathBuf::from("/cli")));
+    }
+}
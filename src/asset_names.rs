@@ -0,0 +1,122 @@
+//! Description:
+//!
+//! Names of the asset files, and of the frames and animations within them, used by the game. Kept
+//! in one place, so that si-check-assets can check that every one of them exists.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+/// sprite sheet's JSON, relative to the asset root
+pub const SHEET_JSON: &str = "sprite-sheet.json";
+/// sprite sheet's image, relative to the asset root
+pub const SHEET_IMAGE: &str = "sprite-sheet.png";
+/// animations' JSON, relative to the asset root
+pub const ANIMATIONS_JSON: &str = "sprite-animation.json";
+
+//------------------------------------------------------------------------------
+// Frames used by text
+//------------------------------------------------------------------------------
+
+/// frames for the digits 0 to 9, in order
+pub const DIGIT_FRAMES: [&str; 10] = [
+    "text_0.png",
+    "text_1.png",
+    "text_2.png",
+    "text_3.png",
+    "text_4.png",
+    "text_5.png",
+    "text_6.png",
+    "text_7.png",
+    "text_8.png",
+    "text_9.png",
+];
+
+/// frames for the letters of GAME OVER, in the order indexed by GameOver
+pub const GAME_OVER_FRAMES: [&str; 7] = [
+    "letter_g.png",
+    "letter_a.png",
+    "letter_m.png",
+    "letter_e.png",
+    "letter_o.png",
+    "letter_v.png",
+    "letter_r.png",
+];
+
+pub const SCORE_FRAME: &str = "score.png";
+pub const SCORE_PLAYER1_FRAME: &str = "score_1.png";
+pub const SCORE_PLAYER2_FRAME: &str = "score_2.png";
+pub const HI_SCORE_FRAME: &str = "hi_score.png";
+pub const CREDIT_FRAME: &str = "credit.png";
+
+/// frames for the score table's text
+const SCORE_TEXT_FRAMES: [&str; 5] = [
+    SCORE_FRAME,
+    SCORE_PLAYER1_FRAME,
+    SCORE_PLAYER2_FRAME,
+    HI_SCORE_FRAME,
+    CREDIT_FRAME,
+];
+
+//------------------------------------------------------------------------------
+// Frames used by the world
+//------------------------------------------------------------------------------
+
+pub const ALIEN_BULLET2_FRAME: &str = "alien_bullet2.png";
+pub const PLAYER_FRAME: &str = "Player.png";
+pub const PLAYER_BULLET_FRAME: &str = "player_bullet_small.png";
+pub const PLAYER_BULLET_EXPLOSION_FRAME: &str = "bullet_top_bang.png";
+pub const BARRIER_FRAME: &str = "barrier.png";
+pub const ALIEN_BULLET_EXPLOSION_WITH_PLAYER_BULLET_FRAME: &str = "alien_bullet_explosiion_with_player_bullet.png";
+pub const ALIEN_BULLET_EXPLOSION_FRAME: &str = "alien_bullet_explosiion.png";
+pub const BARRIER_EXPLOSION_MASK_FRAME: &str = "bullet_barrier_mask.png";
+pub const ALIEN_EXPLOSION_FRAME: &str = "alien_explosion.png";
+pub const UFO_EXPLOSION_FRAME: &str = "ufo_explosion.png";
+pub const SHIP_FRAME: &str = "ship.png";
+pub const SPLASH_FRAME: &str = "splash.png";
+
+/// frames used directly by the world, rather than through an animation
+const WORLD_FRAMES: [&str; 12] = [
+    ALIEN_BULLET2_FRAME,
+    PLAYER_FRAME,
+    PLAYER_BULLET_FRAME,
+    PLAYER_BULLET_EXPLOSION_FRAME,
+    BARRIER_FRAME,
+    ALIEN_BULLET_EXPLOSION_WITH_PLAYER_BULLET_FRAME,
+    ALIEN_BULLET_EXPLOSION_FRAME,
+    BARRIER_EXPLOSION_MASK_FRAME,
+    ALIEN_EXPLOSION_FRAME,
+    UFO_EXPLOSION_FRAME,
+    SHIP_FRAME,
+    SPLASH_FRAME,
+];
+
+//------------------------------------------------------------------------------
+// Animations
+//------------------------------------------------------------------------------
+
+pub const ALIEN1_ANIMATION: &str = "Alien1";
+pub const ALIEN2_ANIMATION: &str = "Alien2";
+pub const ALIEN3_ANIMATION: &str = "Alien3";
+pub const ALIEN_BULLET1_ANIMATION: &str = "AlienBullet1";
+pub const ALIEN_BULLET3_ANIMATION: &str = "AlienBullet3";
+pub const PLAYER_EXPLOSION_ANIMATION: &str = "PlayerExplosion";
+
+/// every animation used by the game
+#[allow(dead_code)] // used by si-check-assets
+pub const ANIMATIONS: [&str; 6] = [
+    ALIEN1_ANIMATION,
+    ALIEN2_ANIMATION,
+    ALIEN3_ANIMATION,
+    ALIEN_BULLET1_ANIMATION,
+    ALIEN_BULLET3_ANIMATION,
+    PLAYER_EXPLOSION_ANIMATION,
+];
+
+/// returns every frame used directly by the game, rather than through an animation
+#[allow(dead_code)] // used by si-check-assets
+pub fn frames() -> impl Iterator<Item = &'static str> {
+    DIGIT_FRAMES.iter()
+        .chain(GAME_OVER_FRAMES.iter())
+        .chain(SCORE_TEXT_FRAMES.iter())
+        .chain(WORLD_FRAMES.iter())
+        .copied()
+}
@@ -0,0 +1,199 @@
+//! Description:
+//!
+//! Sprites, animations and text the game draws. They are loaded once, and never change, so they are kept 
+//! apart from the game's state. Entities refer to their sprites by handle, so that they remain plain data.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::collections::HashMap;
+use either::*;
+
+use crate::sprite_sheet::{SpriteSheet, SheetJSON, AnimationJSON, Sprite, SpriteMask};
+use crate::animation::*;
+use crate::text::*;
+use crate::asset::*;
+use crate::asset_names::*;
+
+/// handle to a sprite in the assets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpriteHandle(usize);
+
+pub struct Assets {
+    /// sprites referred to by handle, e.g. by entities
+    sprites: Vec<Sprite>,
+
+    /// splash screen
+    splash: Sprite,
+
+    /// text stuff
+    digits: Digits,
+
+    /// score text stuff
+    score_text: Score,
+
+    /// game over text
+    game_over: GameOver,
+
+    /// explosion when player bullet hits top of internal screen
+    player_bullet_explosion: SpriteHandle,
+
+    /// explosion when player is hit by alien bullet
+    player_explosion: Animation,
+
+    /// shield bullet explosion mask
+    shield_bullet_explosion_mask: SpriteMask,
+
+    /// explosion when alien bullet hits the ground
+    alien_bullet_explosion: SpriteHandle,
+
+    /// explosion displayed in an alien's place when it is hit by the player's bullet
+    alien_explosion: Sprite,
+
+    /// explosion displayed in the ship's place when it is hit by the player's bullet
+    ufo_explosion: SpriteHandle,
+
+    /// explosion when alien bullet hits player's bullet
+    alien_bullet_explosiion_with_player_bullet: SpriteHandle,
+}
+
+impl Assets {
+    /// load assets, any problems are added to the report
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_json` - Description of sprite sheet
+    /// * `anis_json` - Animations within sprite sheet
+    /// * `sprite_sheet` - Sprite sheet sprites are decoded from
+    /// * `report` - Report of any assets that failed to load
+    pub fn load(
+        sheet_json: &SheetJSON,
+        anis_json: &HashMap<String, AnimationJSON>,
+        sprite_sheet: &SpriteSheet,
+        report: &mut AssetReport) -> Self {
+
+        // bullet explosion mask, used to cut out holes from sheild when it bullet collides
+        let barrier_explosion_sprite_mask = report.frame(sheet_json, BARRIER_EXPLOSION_MASK_FRAME);
+        let barrier_explosion_sprite_mask = Sprite::from_json(&barrier_explosion_sprite_mask, sprite_sheet);
+        let shield_bullet_explosion_mask = barrier_explosion_sprite_mask.create_mask();
+
+        let alien_explosion_sprite = report.frame(sheet_json, ALIEN_EXPLOSION_FRAME);
+        //let explosion_sprite = sheet_json.frames.get("block.png").unwrap();
+        let alien_explosion = Sprite::from_json(&alien_explosion_sprite, sprite_sheet);
+
+        let s = report.frame(sheet_json, SPLASH_FRAME);
+        let splash = Sprite::from_json(&s, sprite_sheet);
+
+        let mut assets = Assets {
+            sprites: vec![],
+            splash,
+            // load text
+            digits: Digits::new(sheet_json, sprite_sheet, report),
+            score_text: Score::new(sheet_json, sprite_sheet, report),
+            game_over: GameOver::new(sheet_json, sprite_sheet, report),
+            player_bullet_explosion: SpriteHandle::default(),
+            player_explosion: Animation::default(),
+            shield_bullet_explosion_mask,
+            alien_bullet_explosion: SpriteHandle::default(),
+            alien_explosion,
+            ufo_explosion: SpriteHandle::default(),
+            alien_bullet_explosiion_with_player_bullet: SpriteHandle::default(),
+        };
+
+        // explosions are added to the game as entities, so they refer to their sprites by handle
+        let explosion_sprite = report.frame(sheet_json, PLAYER_BULLET_EXPLOSION_FRAME);
+        //let explosion_sprite = sheet_json.frames.get("block.png").unwrap();
+        assets.player_bullet_explosion = assets.add_sprite(Sprite::from_json(&explosion_sprite, sprite_sheet));
+
+        let explosion_sprite = report.frame(sheet_json, ALIEN_BULLET_EXPLOSION_WITH_PLAYER_BULLET_FRAME);
+        assets.alien_bullet_explosiion_with_player_bullet = 
+            assets.add_sprite(Sprite::from_json(&explosion_sprite, sprite_sheet));
+
+        assets.player_explosion = Animation::new(
+            &report.animation(anis_json, PLAYER_EXPLOSION_ANIMATION), sheet_json, sprite_sheet, &mut assets, report);
+
+        let explosion_sprite = report.frame(sheet_json, ALIEN_BULLET_EXPLOSION_FRAME);
+        assets.alien_bullet_explosion = assets.add_sprite(Sprite::from_json(&explosion_sprite, sprite_sheet));
+
+        let s = report.frame(sheet_json, UFO_EXPLOSION_FRAME);
+        assets.ufo_explosion = assets.add_sprite(Sprite::from_json(&s, sprite_sheet));
+
+        assets
+    }
+
+    /// add a sprite, returning its handle
+    ///
+    /// # Arguments
+    ///
+    /// * `sprite` - Sprite to add
+    pub fn add_sprite(&mut self, sprite: Sprite) -> SpriteHandle {
+        self.sprites.push(sprite);
+        SpriteHandle(self.sprites.len() - 1)
+    }
+
+    /// returns the sprite for a handle
+    #[inline]
+    pub fn get_sprite(&self, handle: SpriteHandle) -> &Sprite {
+        &self.sprites[handle.0]
+    }
+
+    /// returns the mask of the hole a bullet makes in a barrier
+    #[inline]
+    pub fn get_shield_bullet_explosion_mask(&self) -> &SpriteMask {
+        &self.shield_bullet_explosion_mask
+    }
+
+    /// digits for drawing numbers
+    #[inline]
+    pub fn get_digits(&self) -> &Digits {
+        &self.digits
+    }
+
+    /// score text for drawing
+    #[inline]
+    pub fn get_score_text(&self) -> &Score {
+        &self.score_text
+    }
+
+    /// game over text for drawing
+    #[inline]
+    pub fn get_game_over(&self) -> &GameOver {
+        &self.game_over
+    }
+
+    #[inline]
+    pub fn get_splash_screen_sprite(&self) -> Sprite {
+        self.splash.clone()
+    }
+
+    #[inline]
+    pub fn get_player_explosion_sprite(&self) -> Either<SpriteHandle,Animation> {
+        Right(self.player_explosion.clone())
+    }
+
+    #[inline]
+    pub fn get_player_bullet_explosion_sprite(&self) -> Either<SpriteHandle,Animation> {
+        Left(self.player_bullet_explosion)
+    }
+
+    #[inline]
+    pub fn get_alien_bullet_explosion_sprite(&self) -> Either<SpriteHandle,Animation> {
+        Left(self.alien_bullet_explosion)
+    }
+
+    /// returns the explosion displayed in an alien's place when it is hit
+    #[inline]
+    pub fn get_alien_explosion_sprite(&self) -> &Sprite {
+        &self.alien_explosion
+    }
+
+    /// returns the explosion displayed in the ship's place when it is hit
+    #[inline]
+    pub fn get_ufo_explosion_sprite(&self) -> SpriteHandle {
+        self.ufo_explosion
+    }
+
+    #[inline]
+    pub fn get_alien_bullet_explosiion_with_player_bullet(&self) -> Either<SpriteHandle,Animation> {
+        Left(self.alien_bullet_explosiion_with_player_bullet)
+    }
+}
@@ -1,10 +1,69 @@
 //! Description: 
 //! 
+//! Audio system, plays sound effects in response to the game events raised during the current frame.
 //! 
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 
-use crate::entity::*;
+use crate::event::*;
 use crate::world::*;
 
+/// play any sound effects for this frame's events
+///
+/// # Arguments
+///
+/// * `world` - The game world
 pub fn audio_system(world: &World) {
-}
\ No newline at end of file
+    for event in world.get_state().get_events() {
+        match event {
+            GameEvent::MarchStep { note } => world.play_march_note(note),
+            GameEvent::ShotFired { x } => world.play_player_shot(x),
+            GameEvent::AlienKilled { x, .. } => world.play_alien_explosion(x),
+            GameEvent::PlayerKilled => world.play_player_explosion(),
+            GameEvent::UfoSpawned { x } => world.play_ufo(x),
+            GameEvent::UfoMoved { x } => world.move_ufo_sound(x),
+            GameEvent::UfoEscaped | GameEvent::LevelCleared => world.pause_ufo(),
+            GameEvent::UfoKilled { .. } => world.play_ufo_hit(),
+            GameEvent::ExtraLife => world.play_extra_life(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_backend::*;
+    use crate::audio_backend::tests::SharedWav;
+    use crate::game_state::tests::new_game_state;
+    use crate::interface::Interface;
+    use crate::mixer::MIX_CHANNELS;
+    use crate::sound::Sound;
+
+    #[test]
+    fn effects_are_captured_at_their_tick() {
+        const SHOT_TICK: usize = 5;
+        const TICKS: usize = 10;
+
+        let wav = SharedWav::default();
+        let backend = CaptureBackend::from_writer(wav.clone()).unwrap();
+        let sound = Sound::new_synth(Box::new(backend), Interface::get_width() * 4);
+        let (assets, state) = new_game_state();
+        let mut world = World::new(assets, state, sound);
+
+        // run the audio, as the main loop does, firing a shot on one tick
+        for tick in 0..TICKS {
+            if tick == SHOT_TICK {
+                world.get_mut_state().push_event(GameEvent::ShotFired { x: 100 });
+            }
+            audio_system(&world);
+            world.update_sound();
+            world.get_mut_state().update();
+        }
+        world.finish_sound().unwrap();
+
+        let samples = wav.samples();
+        let tick = FRAMES_PER_TICK * MIX_CHANNELS as usize;
+        assert_eq!(samples.len(), TICKS * tick);
+        assert!(samples[..SHOT_TICK * tick].iter().all(|s| *s == 0));
+        assert!(samples[SHOT_TICK * tick..(SHOT_TICK + 1) * tick].iter().any(|s| *s != 0));
+    }
+}
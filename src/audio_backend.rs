@@ -0,0 +1,338 @@
+//! Description:
+//!
+//! Audio backends, which provide the sinks that sounds are played through. Normally this is
+//! an audio device, via rodio, but when no device exists, e.g. CI containers, a silent null
+//! backend is used instead. There is also a capture backend, which mixes everything played
+//! and writes it out, as it goes, as a WAV file.
+//!
+//! The sinks of both the device and capture backends are mixed into a single, limited, stream.
+//!
+//! The backend can be chosen with the environment variable SPACE_INVADERS_AUDIO, set to
+//! `null` or `capture`, otherwise the device is used if there is one. The file written by
+//! the capture backend is set with SPACE_INVADERS_AUDIO_CAPTURE.
+//!
+//! A session's audio can also be exported with the command line option `--export-audio <file>`,
+//! which uses the capture backend, bypassing the audio device entirely. The capture backend mixes
+//! a fixed number of frames each game tick, so the export is sample accurate to the tick, however
+//! fast the game actually ran.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
+use std::sync::Arc;
+
+use rodio::{Device, Sink};
+use rodio::dynamic_mixer::{DynamicMixer, DynamicMixerController};
+
+use crate::mixer::*;
+use crate::config::command_line_option;
+
+/// environment variable used to choose the audio backend
+const AUDIO_BACKEND_ENV: &str = "SPACE_INVADERS_AUDIO";
+/// environment variable used to set the file written by the capture backend
+const AUDIO_CAPTURE_ENV: &str = "SPACE_INVADERS_AUDIO_CAPTURE";
+/// file written by the capture backend, if not set in the environment
+const DEFAULT_CAPTURE_FILE: &str = "space-invaders.wav";
+/// command line option used to export a session's audio to a WAV file
+pub const EXPORT_AUDIO_ARG: &str = "--export-audio";
+/// game ticks per second, i.e. frames drawn, matching the arcade's 60Hz
+pub const TICKS_PER_SECOND: u32 = 60;
+/// frames of audio mixed by the capture backend each game tick
+pub const FRAMES_PER_TICK: usize = (MIX_SAMPLE_RATE / TICKS_PER_SECOND) as usize;
+
+/// provider of sinks for sound playback
+pub trait AudioBackend {
+    /// name of backend, for logging
+    fn name(&self) -> &'static str;
+
+    /// create a new sink, sounds appended to a sink are played one after another
+    fn new_sink(&mut self) -> Sink;
+
+    /// called once per game tick, backends that do not play through a device mix the tick's audio here
+    fn update(&mut self) { }
+
+    /// called once the game is exiting, e.g. to write out captured audio
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// backend that plays through an audio device
+pub struct RodioBackend {
+    /// sinks are added to the mix, which is played through the device
+    mix: Arc<DynamicMixerController<f32>>,
+    /// sink playing the mix, kept so that it is not dropped
+    _device_sink: Sink,
+}
+
+impl RodioBackend {
+    /// create a backend for the default audio device, if there is one
+    pub fn new() -> Option<Self> {
+        rodio::default_output_device().map(|device: Device| {
+            let (mix, output) = limited_mix();
+            let device_sink = Sink::new(&device);
+            device_sink.append(output);
+            RodioBackend { mix, _device_sink: device_sink }
+        })
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn name(&self) -> &'static str {
+        "rodio"
+    }
+
+    fn new_sink(&mut self) -> Sink {
+        let (sink, output) = Sink::new_idle();
+        self.mix.add(output);
+        sink
+    }
+}
+
+/// silent backend, sounds appended to its sinks are never played
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn name(&self) -> &'static str {
+        "null"
+    }
+
+    fn new_sink(&mut self) -> Sink {
+        // nothing reads from the sink's queue, so it stays silent
+        let (sink, _) = Sink::new_idle();
+        sink
+    }
+}
+
+/// backend that mixes all sinks, a game tick at a time, writing the mixed audio as a WAV file as it goes,
+/// so that a long session is not held in memory, and what was captured survives the game being killed
+pub struct CaptureBackend<W: Write + Seek> {
+    /// sinks are added to the mix
+    mix: Arc<DynamicMixerController<f32>>,
+    /// limited output of the mix
+    output: Limiter<DynamicMixer<f32>>,
+    /// writer mixed samples are written to, until finished or writing fails
+    writer: Option<hound::WavWriter<W>>,
+    /// first error writing samples, reported when finished
+    error: Option<io::Error>,
+}
+
+/// returns a hound error as an IO error
+fn to_io_error(error: hound::Error) -> io::Error {
+    match error {
+        hound::Error::IoError(error) => error,
+        error => io::Error::other(error.to_string()),
+    }
+}
+
+impl CaptureBackend<BufWriter<File>> {
+    /// create a capture backend writing to a file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - WAV file captured audio is written to
+    pub fn new(path: &str) -> io::Result<Self> {
+        log::info!("capturing audio to {}", path);
+        Self::from_writer(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> CaptureBackend<W> {
+    /// create a capture backend
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Writer captured audio is written to, as a WAV file
+    pub fn from_writer(writer: W) -> io::Result<Self> {
+        let spec = hound::WavSpec {
+            channels: MIX_CHANNELS,
+            sample_rate: MIX_SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let (mix, output) = limited_mix();
+        Ok(CaptureBackend {
+            mix,
+            output,
+            writer: Some(hound::WavWriter::new(writer, spec).map_err(to_io_error)?),
+            error: None,
+        })
+    }
+
+    /// mix the next frames of all sinks, writing them out
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - number of frames, i.e. samples per channel, to mix
+    pub fn mix(&mut self, frames: usize) {
+        for _ in 0..frames * MIX_CHANNELS as usize {
+            let sample = self.output.next().unwrap_or(0.0);
+            if let Some(writer) = self.writer.as_mut() {
+                if let Err(error) = writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16) {
+                    // stop capturing, the error is reported once finished
+                    log::warn!("unable to write captured audio: {}", error);
+                    self.error = Some(to_io_error(error));
+                    self.writer = None;
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write + Seek> AudioBackend for CaptureBackend<W> {
+    fn name(&self) -> &'static str {
+        "capture"
+    }
+
+    fn new_sink(&mut self) -> Sink {
+        let (sink, output) = Sink::new_idle();
+        self.mix.add(output);
+        sink
+    }
+
+    fn update(&mut self) {
+        // each tick is the same length in the mix, whatever time it actually took
+        self.mix(FRAMES_PER_TICK);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        match self.writer.take() {
+            Some(writer) => writer.finalize().map_err(to_io_error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// returns a capture backend writing to a file, or if the file cannot be created the null backend
+///
+/// # Arguments
+///
+/// * `path` - WAV file captured audio is written to
+fn capture_backend(path: &str) -> Box<dyn AudioBackend> {
+    match CaptureBackend::new(path) {
+        Ok(backend) => Box::new(backend),
+        Err(error) => {
+            log::warn!("unable to capture audio to {}: {}, sound disabled", path, error);
+            Box::new(NullBackend)
+        },
+    }
+}
+
+/// create the audio backend, as chosen on the command line or in the environment, falling back to 
+/// the null backend if there is no audio device
+pub fn create_audio_backend() -> Box<dyn AudioBackend> {
+    if let Some(path) = command_line_option(EXPORT_AUDIO_ARG) {
+        log::info!("exporting session audio to {}", path);
+        return capture_backend(&path);
+    }
+
+    let backend: Box<dyn AudioBackend> = match std::env::var(AUDIO_BACKEND_ENV).as_deref() {
+        Ok("null") => Box::new(NullBackend),
+        Ok("capture") => {
+            let path = std::env::var(AUDIO_CAPTURE_ENV).unwrap_or_else(|_| DEFAULT_CAPTURE_FILE.to_string());
+            capture_backend(&path)
+        },
+        _ => match RodioBackend::new() {
+            Some(backend) => Box::new(backend),
+            None => {
+                log::warn!("no audio device found, sound disabled");
+                Box::new(NullBackend)
+            },
+        },
+    };
+
+    log::info!("using {} audio backend", backend.name());
+    backend
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::{Cursor, SeekFrom};
+    use std::rc::Rc;
+    use rodio::buffer::SamplesBuffer;
+
+    /// in memory WAV file, shared so that it can be read once a capture backend has finished with it
+    #[derive(Clone, Default)]
+    pub struct SharedWav(Rc<RefCell<Cursor<Vec<u8>>>>);
+
+    impl SharedWav {
+        /// returns the samples written
+        pub fn samples(&self) -> Vec<i16> {
+            captured_samples(self.0.borrow().get_ref())
+        }
+    }
+
+    impl Write for SharedWav {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.borrow_mut().flush()
+        }
+    }
+
+    impl Seek for SharedWav {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.0.borrow_mut().seek(pos)
+        }
+    }
+
+    /// returns the samples of a captured WAV file, checking it is in the mix's format
+    ///
+    /// # Arguments
+    ///
+    /// * `wav` - Captured WAV file
+    fn captured_samples(wav: &[u8]) -> Vec<i16> {
+        let mut reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, MIX_CHANNELS);
+        assert_eq!(spec.sample_rate, MIX_SAMPLE_RATE);
+        assert_eq!(spec.bits_per_sample, 16);
+        reader.samples::<i16>().map(|s| s.unwrap()).collect()
+    }
+
+    #[test]
+    fn capture_mixes_a_tick_at_a_time() {
+        let mut wav = Cursor::new(vec![]);
+        let mut backend = CaptureBackend::from_writer(&mut wav).unwrap();
+
+        // nothing playing, so the first tick is silent
+        backend.update();
+        let sink = backend.new_sink();
+        sink.append(SamplesBuffer::new(MIX_CHANNELS, MIX_SAMPLE_RATE, vec![0.5f32; FRAMES_PER_TICK * 2]));
+        backend.update();
+        backend.finish().unwrap();
+        drop(backend);
+
+        let samples = captured_samples(wav.get_ref());
+        let tick = FRAMES_PER_TICK * MIX_CHANNELS as usize;
+        assert_eq!(samples.len(), 2 * tick);
+        assert!(samples[..tick].iter().all(|s| *s == 0));
+        assert!(samples[tick..].iter().any(|s| *s != 0));
+    }
+
+    #[test]
+    fn capture_writes_wav_file() {
+        let path = std::env::temp_dir().join(format!("space-invaders-capture-{}.wav", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut backend = CaptureBackend::new(path).unwrap();
+        let sink = backend.new_sink();
+        sink.append(SamplesBuffer::new(MIX_CHANNELS, MIX_SAMPLE_RATE, vec![0.25f32; FRAMES_PER_TICK]));
+        for _ in 0..3 {
+            backend.update();
+        }
+        backend.finish().unwrap();
+
+        let samples = captured_samples(&std::fs::read(path).unwrap());
+        assert_eq!(samples.len(), 3 * FRAMES_PER_TICK * MIX_CHANNELS as usize);
+        std::fs::remove_file(path).unwrap();
+    }
+}
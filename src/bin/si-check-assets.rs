@@ -0,0 +1,328 @@
+//! Description:
+//!
+//! Asset checker, for use before committing changes to the assets and in CI. Checks that:
+//!
+//!   * the sprite sheet's JSON, the animations' JSON, and the sprite sheet's image can all be read
+//!   * the image is the size given in the JSON, and every frame lies within it
+//!   * every frame used by the game, either directly or by an animation, is in the sprite sheet
+//!   * every animation used by the game exists, and its frames are all the same size, as an
+//!     animation's bounding box is taken from its frames
+//!   * every sound in the sound pack can be decoded
+//!
+//!     si-check-assets [--assets <dir>] [--sound-pack <file>]...
+//!
+//! Assets are checked in `assets`, by default, and sound packs are relative to it. Every problem
+//! found is listed, and the exit code is non-zero if there were any.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+#![forbid(unsafe_code)]
+#![allow(non_snake_case)]
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::GenericImageView;
+use rodio::Source;
+use serde::de::DeserializeOwned;
+
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[path = "../sprite_sheet_json.rs"]
+mod sprite_sheet_json;
+use sprite_sheet_json::*;
+
+#[allow(dead_code)]
+#[path = "../sound_pack_json.rs"]
+mod sound_pack_json;
+use sound_pack_json::*;
+
+#[allow(dead_code)]
+#[path = "../asset_names.rs"]
+mod asset_names;
+
+#[path = "../sound_check.rs"]
+mod sound_check;
+
+/// directory checked, if not given on the command line
+const DEFAULT_ASSETS: &str = "assets";
+/// sound pack checked, if none are given on the command line
+const DEFAULT_SOUND_PACK: &str = "sound-pack.json";
+
+/// command line options
+struct Options {
+    assets: PathBuf,
+    sound_packs: Vec<String>,
+}
+
+/// what was checked, and the problems found
+#[derive(Default)]
+struct Report {
+    assets: String,
+    checked: Vec<String>,
+    problems: Vec<String>,
+}
+
+impl Report {
+    /// note something that was checked, for the report's summary
+    fn checked(&mut self, summary: String) {
+        self.checked.push(summary);
+    }
+
+    /// add a problem to the report
+    fn problem(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "checked assets in {}:", self.assets)?;
+        for summary in &self.checked {
+            writeln!(f, "  {}", summary)?;
+        }
+        if self.problems.is_empty() {
+            return writeln!(f, "no problems found");
+        }
+        writeln!(f, "{} problem(s) found:", self.problems.len())?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("si-check-assets: {}", error);
+            std::process::exit(2);
+        },
+    };
+
+    let mut report = Report {
+        assets: options.assets.display().to_string(),
+        ..Default::default()
+    };
+    check_sprites(&options.assets, &mut report);
+    for pack in &options.sound_packs {
+        check_sound_pack(&options.assets, pack, &mut report);
+    }
+
+    print!("{}", report);
+    if !report.problems.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// parse command line options
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        assets: PathBuf::from(DEFAULT_ASSETS),
+        sound_packs: vec![],
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--assets" => options.assets = PathBuf::from(value("--assets")?),
+            "--sound-pack" => options.sound_packs.push(value("--sound-pack")?),
+            "--help" | "-h" => {
+                println!("usage: si-check-assets [--assets <dir>] [--sound-pack <file>]...");
+                std::process::exit(0);
+            },
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if options.sound_packs.is_empty() {
+        options.sound_packs.push(DEFAULT_SOUND_PACK.to_string());
+    }
+    Ok(options)
+}
+
+/// read a JSON asset, adding a problem to the report if it cannot be read
+///
+/// # Arguments
+///
+/// * `path` - JSON file to read
+/// * `report` - Report to add problems to
+fn read_json<T: DeserializeOwned>(path: &Path, report: &mut Report) -> Option<T> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(error) => {
+            report.problem(format!("unable to read {}: {}", path.display(), error));
+            return None;
+        },
+    };
+    serde_json::from_slice(&data)
+        .map_err(|error| report.problem(format!("{} is not valid: {}", path.display(), error)))
+        .ok()
+}
+
+/// returns true if a frame, which may be rotated, lies within an image of the given size
+fn frame_inside(s: &SpriteJSON, width: i32, height: i32) -> bool {
+    let (w, h) = s.sheet_size();
+    s.frame.x >= 0 && s.frame.y >= 0 && s.frame.x + w <= width && s.frame.y + h <= height
+}
+
+/// check the sprite sheet, its image, and the animations
+///
+/// # Arguments
+///
+/// * `assets` - Directory assets are in
+/// * `report` - Report to add problems to
+fn check_sprites(assets: &Path, report: &mut Report) {
+    let sheet_path = assets.join(asset_names::SHEET_JSON);
+    let image_path = assets.join(asset_names::SHEET_IMAGE);
+    let animations_path = assets.join(asset_names::ANIMATIONS_JSON);
+
+    let sheet_json: Option<SheetJSON> = read_json(&sheet_path, report);
+    let image_size = match image::open(&image_path) {
+        Ok(image) => Some(image.dimensions()),
+        Err(error) => {
+            report.problem(format!("unable to read {}: {}", image_path.display(), error));
+            None
+        },
+    };
+    let animations: Option<BTreeMap<String, AnimationJSON>> = read_json(&animations_path, report);
+
+    // without the sheet's JSON there are no frames to check
+    let sheet_json = match sheet_json {
+        Some(sheet_json) => sheet_json,
+        None => return,
+    };
+    let sheet = asset_names::SHEET_IMAGE;
+    report.checked(format!(
+        "sprite sheet {}: {} frames, {}x{}",
+        asset_names::SHEET_JSON,
+        sheet_json.frames.len(),
+        sheet_json.meta.size.w,
+        sheet_json.meta.size.h));
+
+    // frames are checked against the image, if it could be read, otherwise against the size it should be
+    let (width, height) = match image_size {
+        Some((w, h)) => {
+            let expected = (sheet_json.meta.size.w, sheet_json.meta.size.h);
+            if (w as i32, h as i32) != expected {
+                report.problem(format!(
+                    "sprite sheet {} is {}x{}, but should be {}x{}",
+                    sheet, w, h, expected.0, expected.1));
+            }
+            (w as i32, h as i32)
+        },
+        None => (sheet_json.meta.size.w, sheet_json.meta.size.h),
+    };
+
+    let mut names: Vec<&String> = sheet_json.frames.keys().collect();
+    names.sort();
+    for name in names {
+        let s = &sheet_json.frames[name];
+        let frame = &s.frame;
+        if !frame_inside(s, width, height) {
+            report.problem(format!(
+                "frame \"{}\" ({}x{} at {},{}) lies outside of sprite sheet {} ({}x{})",
+                name, frame.w, frame.h, frame.x, frame.y, sheet, width, height));
+        }
+    }
+
+    for name in asset_names::frames() {
+        if !sheet_json.frames.contains_key(name) {
+            report.problem(format!("frame \"{}\", used by the game, is missing from sprite sheet {}", name, sheet));
+        }
+    }
+
+    let animations = match animations {
+        Some(animations) => animations,
+        None => return,
+    };
+    report.checked(format!("animations {}: {} animations", asset_names::ANIMATIONS_JSON, animations.len()));
+
+    for name in asset_names::ANIMATIONS.iter() {
+        if !animations.contains_key(*name) {
+            report.problem(format!("animation \"{}\", used by the game, is missing", name));
+        }
+    }
+
+    for (name, animation) in &animations {
+        if animation.frames.is_empty() {
+            report.problem(format!("animation \"{}\" has no frames", name));
+            continue;
+        }
+
+        let mut frames = vec![];
+        for frame in &animation.frames {
+            match sheet_json.frames.get(frame) {
+                Some(s) => frames.push((frame, s.sourceSize.w, s.sourceSize.h)),
+                None => report.problem(format!(
+                    "frame \"{}\", used by animation \"{}\", is missing from sprite sheet {}", frame, name, sheet)),
+            }
+        }
+
+        // the animation's bounding box is the size of its last frame, before trimming, so every frame must be
+        // the same size
+        if frames.windows(2).any(|f| (f[0].1, f[0].2) != (f[1].1, f[1].2)) {
+            let sizes: Vec<String> = frames.iter().map(|(frame, w, h)| format!("{} {}x{}", frame, w, h)).collect();
+            report.problem(format!(
+                "frames of animation \"{}\" are not all the same size: {}",
+                name,
+                sizes.join(", ")));
+        }
+    }
+}
+
+/// check a sound pack, and that each of its sounds can be decoded
+///
+/// # Arguments
+///
+/// * `assets` - Directory assets are in
+/// * `pack` - Sound pack's JSON, relative to `assets`
+/// * `report` - Report to add problems to
+fn check_sound_pack(assets: &Path, pack: &str, report: &mut Report) {
+    let path = assets.join(pack);
+    let json: SoundPackJSON = match read_json(&path, report) {
+        Some(json) => json,
+        None => return,
+    };
+    report.checked(format!("sound pack {}: \"{}\", {} sounds", pack, json.name, json.sounds.len()));
+
+    for event in SOUND_PACK_EVENTS.iter() {
+        if !json.sounds.contains_key(*event) {
+            report.problem(format!("no sound for event \"{}\" in sound pack {}", event, pack));
+        }
+    }
+
+    // files are relative to the pack
+    let root = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut events: Vec<&String> = json.sounds.keys().collect();
+    events.sort();
+    for event in events {
+        let file = root.join(&json.sounds[event].file);
+        if let Err(error) = decode_sound(&file) {
+            report.problem(format!("sound for event \"{}\", {}, cannot be played: {}", event, file.display(), error));
+        }
+    }
+}
+
+/// decode a sound, in the same way as the game, returning why if it cannot be
+///
+/// # Arguments
+///
+/// * `file` - Sound file
+fn decode_sound(file: &Path) -> Result<(), String> {
+    let data = std::fs::read(file).map_err(|e| e.to_string())?;
+
+    let decoder = sound_check::decoder(data)?;
+    if decoder.channels() == 0 || decoder.sample_rate() == 0 {
+        return Err("no channels or sample rate".to_string());
+    }
+    if decoder.count() == 0 {
+        return Err("no samples".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,473 @@
+//! Description:
+//!
+//! Sprite sheet packer, replacing TexturePacker. Packs loose PNGs into a single sprite sheet image,
+//! and writes the JSON describing each frame, in the same format as TexturePacker, which the game
+//! already reads.
+//!
+//!     si-pack [--output <sheet>] [--extrude <px>] [--padding <px>] [--trim] [--exclude <file>]... [<png or dir>]...
+//!
+//! Directories are searched for PNGs, not recursively, by default `assets`. Files to leave out, e.g.
+//! screen shots, are given with `--exclude`, a trailing `*` matches any file starting with the name.
+//! The sheet is written to `<sheet>.png` and `<sheet>.json`, by default `assets/sprite-sheet`, creating
+//! `<sheet>`'s directory if it does not exist.
+//!
+//! Packing is deterministic, the same PNGs always give the same sheet, so that changes to the sheet
+//! can be reviewed. Sprites are placed on shelves, tallest first, choosing the sheet width that
+//! gives the smallest area. Each sprite's edge is extruded by 1 pixel, by default, as TexturePacker
+//! was set to do. With `--trim` transparent rows and columns around each sprite are left out of the
+//! sheet, giving a smaller sheet, and the JSON records where the trimmed frame lies within the sprite.
+//! Sprites are never rotated.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+#![forbid(unsafe_code)]
+#![allow(non_snake_case)]
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[path = "../sprite_sheet_json.rs"]
+mod sprite_sheet_json;
+use sprite_sheet_json::*;
+
+/// sheet written, if not given on the command line
+const DEFAULT_OUTPUT: &str = "assets/sprite-sheet";
+/// directory searched for PNGs, if none are given on the command line
+const DEFAULT_INPUT: &str = "assets";
+/// pixels each sprite's edge is copied outwards, so that sampling at the edge does not pick up a neighbour
+const DEFAULT_EXTRUDE: u32 = 1;
+/// empty pixels between sprites, after extrusion
+const DEFAULT_PADDING: u32 = 0;
+
+/// command line options
+struct Options {
+    output: PathBuf,
+    extrude: u32,
+    padding: u32,
+    trim: bool,
+    exclude: Vec<String>,
+    inputs: Vec<PathBuf>,
+}
+
+/// a sprite to be packed
+struct Sprite {
+    /// frame name, the PNG's file name
+    name: String,
+    /// sprite's image, trimmed if required
+    image: RgbaImage,
+    /// position of trimmed image within the sprite
+    offset: (u32, u32),
+    /// size of sprite, before it was trimmed
+    source_size: (u32, u32),
+    /// position of sprite's top left, including extrusion, in the sheet
+    x: u32,
+    y: u32,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("si-pack: {}", error);
+        std::process::exit(1);
+    }
+}
+
+/// pack the sprites given on the command line
+fn run() -> Result<(), String> {
+    let options = parse_options()?;
+
+    let sheet_image = options.output.with_extension("png");
+    let sheet_json = options.output.with_extension("json");
+    let sheet_name = file_name(&sheet_image);
+
+    let mut sprites = load_sprites(&options, &sheet_name)?;
+    if sprites.is_empty() {
+        return Err("no PNGs to pack".to_string());
+    }
+
+    let (width, height) = pack(&mut sprites, options.extrude, options.padding);
+    let sheet = render(&sprites, width, height, options.extrude);
+    let json = sheet_json_string(&sprites, &sheet_name, width, height, options.extrude);
+
+    // make sure the game can read what is written
+    serde_json::from_str::<SheetJSON>(&json)
+        .map_err(|e| format!("generated JSON does not match the sprite sheet format: {}", e))?;
+
+    if let Some(dir) = sheet_image.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+    }
+    sheet.save(&sheet_image).map_err(|e| format!("unable to write {}: {}", sheet_image.display(), e))?;
+    std::fs::write(&sheet_json, json).map_err(|e| format!("unable to write {}: {}", sheet_json.display(), e))?;
+
+    println!(
+        "packed {} sprites into {}x{} sheet {}",
+        sprites.len(),
+        width,
+        height,
+        sheet_image.display());
+    Ok(())
+}
+
+/// parse command line options
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        output: PathBuf::from(DEFAULT_OUTPUT),
+        extrude: DEFAULT_EXTRUDE,
+        padding: DEFAULT_PADDING,
+        trim: false,
+        exclude: vec![],
+        inputs: vec![],
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--output" => options.output = PathBuf::from(value("--output")?),
+            "--extrude" => options.extrude = parse_pixels("--extrude", &value("--extrude")?)?,
+            "--padding" => options.padding = parse_pixels("--padding", &value("--padding")?)?,
+            "--trim" => options.trim = true,
+            "--exclude" => options.exclude.push(value("--exclude")?),
+            "--help" | "-h" => {
+                println!(
+                    "usage: si-pack [--output <sheet>] [--extrude <px>] [--padding <px>] [--trim] [--exclude <file>]... [<png or dir>]...");
+                std::process::exit(0);
+            },
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        options.inputs.push(PathBuf::from(DEFAULT_INPUT));
+    }
+    Ok(options)
+}
+
+/// parse a number of pixels
+fn parse_pixels(name: &str, value: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("{} must be a number of pixels, not {}", name, value))
+}
+
+/// returns a path's file name
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// is a file excluded
+///
+/// # Arguments
+///
+/// * `name` - File name
+/// * `exclude` - Names excluded, a trailing `*` matches any name starting with it
+fn is_excluded(name: &str, exclude: &[String]) -> bool {
+    exclude.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    })
+}
+
+/// load the sprites to be packed, sorted by name
+///
+/// # Arguments
+///
+/// * `options` - Command line options
+/// * `sheet_name` - File name of sheet image, which is never packed into itself
+fn load_sprites(options: &Options, sheet_name: &str) -> Result<Vec<Sprite>, String> {
+    let mut files = BTreeMap::new();
+    for input in &options.inputs {
+        if input.is_dir() {
+            let entries = std::fs::read_dir(input).map_err(|e| format!("unable to read {}: {}", input.display(), e))?;
+            for entry in entries {
+                let path = entry.map_err(|e| format!("unable to read {}: {}", input.display(), e))?.path();
+                if path.extension().is_some_and(|ext| ext == "png") {
+                    files.insert(file_name(&path), path);
+                }
+            }
+        }
+        else {
+            files.insert(file_name(input), input.clone());
+        }
+    }
+
+    let mut sprites = vec![];
+    for (name, path) in files {
+        if name == sheet_name || is_excluded(&name, &options.exclude) {
+            continue;
+        }
+        let image = image::open(&path).map_err(|e| format!("unable to read {}: {}", path.display(), e))?.to_rgba8();
+        let source_size = image.dimensions();
+        let (image, offset) = if options.trim { trim(image) } else { (image, (0, 0)) };
+        sprites.push(Sprite {
+            name,
+            image,
+            offset,
+            source_size,
+            x: 0,
+            y: 0,
+        });
+    }
+    Ok(sprites)
+}
+
+/// returns an image with its transparent rows and columns removed from each edge, and the position of
+/// what is left within the original image
+///
+/// An image that is completely transparent is left as it is.
+///
+/// # Arguments
+///
+/// * `image` - Image to trim
+fn trim(image: RgbaImage) -> (RgbaImage, (u32, u32)) {
+    let opaque: Vec<(u32, u32)> = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[3] != 0)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    if opaque.is_empty() {
+        return (image, (0, 0));
+    }
+
+    let min_x = opaque.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = opaque.iter().map(|p| p.0).max().unwrap_or(0);
+    let min_y = opaque.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = opaque.iter().map(|p| p.1).max().unwrap_or(0);
+    let trimmed = image::imageops::crop_imm(&image, min_x, min_y, max_x - min_x + 1, max_y - min_y + 1).to_image();
+    (trimmed, (min_x, min_y))
+}
+
+/// place sprites on shelves, tallest first, returning the size of the sheet
+///
+/// Every width, from the widest sprite to all sprites side by side, is tried, and the one that gives the
+/// smallest sheet is used, preferring the squarest, and then the narrowest, sheet when there is a tie.
+///
+/// # Arguments
+///
+/// * `sprites` - Sprites to place
+/// * `extrude` - Pixels each sprite's edge is extruded by
+/// * `padding` - Empty pixels between sprites
+fn pack(sprites: &mut [Sprite], extrude: u32, padding: u32) -> (u32, u32) {
+    // sort is stable, and sprites are sorted by name, so ties are always broken the same way
+    sprites.sort_by(|a, b| b.image.height().cmp(&a.image.height()).then(b.image.width().cmp(&a.image.width())));
+
+    let sizes: Vec<(u32, u32)> = sprites
+        .iter()
+        .map(|s| (s.image.width() + extrude * 2 + padding, s.image.height() + extrude * 2 + padding))
+        .collect();
+    let min_width = sizes.iter().map(|s| s.0).max().unwrap_or(0);
+    let max_width = sizes.iter().map(|s| s.0).sum::<u32>();
+
+    let mut best = None;
+    for width in min_width..=max_width {
+        let (_, used_width, height) = place(&sizes, width);
+        let area = used_width as u64 * height as u64;
+        let squareness = (used_width as i64 - height as i64).abs();
+        let key = (area, squareness, used_width);
+        if best.is_none_or(|(best_key, _)| key < best_key) {
+            best = Some((key, width));
+        }
+    }
+
+    let width = best.map(|(_, width)| width).unwrap_or(min_width);
+    let (positions, used_width, height) = place(&sizes, width);
+    for (sprite, (x, y)) in sprites.iter_mut().zip(positions) {
+        sprite.x = x;
+        sprite.y = y;
+    }
+
+    // padding is only needed between sprites, not after the last on each shelf
+    (used_width - padding, height - padding)
+}
+
+/// place sizes on shelves, no wider than a width, returning their positions and the size used
+///
+/// # Arguments
+///
+/// * `sizes` - Sizes to place, in order
+/// * `width` - Widest a shelf can be
+fn place(sizes: &[(u32, u32)], width: u32) -> (Vec<(u32, u32)>, u32, u32) {
+    let mut positions = vec![];
+    let (mut x, mut y, mut shelf_height, mut used_width) = (0, 0, 0, 0);
+    for (w, h) in sizes {
+        if x + w > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions.push((x, y));
+        x += w;
+        shelf_height = shelf_height.max(*h);
+        used_width = used_width.max(x);
+    }
+    (positions, used_width, y + shelf_height)
+}
+
+/// draw sprites into the sheet, extruding their edges
+///
+/// # Arguments
+///
+/// * `sprites` - Placed sprites
+/// * `width` - Width of sheet
+/// * `height` - Height of sheet
+/// * `extrude` - Pixels to extrude each edge by
+fn render(sprites: &[Sprite], width: u32, height: u32, extrude: u32) -> RgbaImage {
+    let mut sheet = RgbaImage::new(width, height);
+    for sprite in sprites {
+        let (w, h) = sprite.image.dimensions();
+        for y in 0..h + extrude * 2 {
+            for x in 0..w + extrude * 2 {
+                // outside of the sprite, the nearest edge pixel is used
+                let sx = (x as i64 - extrude as i64).clamp(0, w as i64 - 1) as u32;
+                let sy = (y as i64 - extrude as i64).clamp(0, h as i64 - 1) as u32;
+                sheet.put_pixel(sprite.x + x, sprite.y + y, *sprite.image.get_pixel(sx, sy));
+            }
+        }
+    }
+    sheet
+}
+
+/// returns the sheet's JSON, laid out as TexturePacker does, with frames sorted by name
+///
+/// # Arguments
+///
+/// * `sprites` - Placed sprites
+/// * `image` - File name of sheet image
+/// * `width` - Width of sheet
+/// * `height` - Height of sheet
+/// * `extrude` - Pixels each edge was extruded by
+fn sheet_json_string(sprites: &[Sprite], image: &str, width: u32, height: u32, extrude: u32) -> String {
+    let frames: BTreeMap<&str, SpriteJSON> = sprites
+        .iter()
+        .map(|sprite| {
+            let (w, h) = (sprite.image.width() as i32, sprite.image.height() as i32);
+            let (source_w, source_h) = (sprite.source_size.0 as i32, sprite.source_size.1 as i32);
+            (sprite.name.as_str(), SpriteJSON {
+                frame: XYWHJSON { x: (sprite.x + extrude) as i32, y: (sprite.y + extrude) as i32, w, h },
+                rotated: false,
+                trimmed: (w, h) != (source_w, source_h),
+                spriteSourceSize: XYWHJSON { x: sprite.offset.0 as i32, y: sprite.offset.1 as i32, w, h },
+                sourceSize: WHJSON { w: source_w, h: source_h },
+            })
+        })
+        .collect();
+
+    let mut json = String::from("{\"frames\": {\n\n");
+    let last = frames.len() - 1;
+    for (i, (name, s)) in frames.iter().enumerate() {
+        let _ = write!(
+            json,
+            "\"{}\":\n{{\n\
+             \t\"frame\": {{\"x\":{},\"y\":{},\"w\":{},\"h\":{}}},\n\
+             \t\"rotated\": {},\n\
+             \t\"trimmed\": {},\n\
+             \t\"spriteSourceSize\": {{\"x\":{},\"y\":{},\"w\":{},\"h\":{}}},\n\
+             \t\"sourceSize\": {{\"w\":{},\"h\":{}}}\n\
+             }}{}\n",
+            name,
+            s.frame.x, s.frame.y, s.frame.w, s.frame.h,
+            s.rotated,
+            s.trimmed,
+            s.spriteSourceSize.x, s.spriteSourceSize.y, s.spriteSourceSize.w, s.spriteSourceSize.h,
+            s.sourceSize.w, s.sourceSize.h,
+            if i == last { "}," } else { "," });
+    }
+    let _ = write!(
+        json,
+        "\"meta\": {{\n\
+         \t\"app\": \"si-pack\",\n\
+         \t\"version\": \"1.0\",\n\
+         \t\"image\": \"{}\",\n\
+         \t\"format\": \"RGBA8888\",\n\
+         \t\"size\": {{\"w\":{},\"h\":{}}},\n\
+         \t\"scale\": \"1\"\n\
+         }}\n\
+         }}\n",
+        image, width, height);
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// returns an unplaced, untrimmed, opaque sprite
+    fn sprite(name: &str, w: u32, h: u32) -> Sprite {
+        Sprite {
+            name: name.to_string(),
+            image: RgbaImage::from_pixel(w, h, image::Rgba([0xFF; 4])),
+            offset: (0, 0),
+            source_size: (w, h),
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// returns sprites of mixed sizes, including ties, sorted by name as they are when loaded
+    fn sprites() -> Vec<Sprite> {
+        vec![
+            sprite("a", 16, 8),
+            sprite("b", 12, 8),
+            sprite("c", 16, 8),
+            sprite("d", 22, 16),
+            sprite("e", 7, 7),
+            sprite("f", 3, 24),
+            sprite("g", 16, 8),
+        ]
+    }
+
+    #[test]
+    fn trim_removes_transparent_edges() {
+        let mut image = RgbaImage::new(8, 6);
+        image.put_pixel(2, 1, image::Rgba([0xFF; 4]));
+        image.put_pixel(5, 3, image::Rgba([0xFF; 4]));
+        let (trimmed, offset) = trim(image);
+        assert_eq!(trimmed.dimensions(), (4, 3));
+        assert_eq!(offset, (2, 1));
+        assert_eq!(trimmed.get_pixel(0, 0).0[3], 0xFF);
+        assert_eq!(trimmed.get_pixel(3, 2).0[3], 0xFF);
+
+        let (empty, offset) = trim(RgbaImage::new(8, 6));
+        assert_eq!(empty.dimensions(), (8, 6));
+        assert_eq!(offset, (0, 0));
+    }
+
+    #[test]
+    fn place_fills_shelves_in_order() {
+        let (positions, width, height) = place(&[(4, 3), (4, 2), (4, 2), (2, 1)], 10);
+        assert_eq!(positions, vec![(0, 0), (4, 0), (0, 3), (4, 3)]);
+        assert_eq!((width, height), (8, 5));
+    }
+
+    #[test]
+    fn pack_is_deterministic_without_overlaps() {
+        const EXTRUDE: u32 = 1;
+        const PADDING: u32 = 2;
+
+        let mut first = sprites();
+        let size = pack(&mut first, EXTRUDE, PADDING);
+        let mut second = sprites();
+        assert_eq!(pack(&mut second, EXTRUDE, PADDING), size);
+        let layout = |sprites: &[Sprite]| sprites.iter().map(|s| (s.name.clone(), s.x, s.y)).collect::<Vec<_>>();
+        assert_eq!(layout(&first), layout(&second));
+
+        // each sprite, with its extrusion, is within the sheet, and padded apart from the others
+        let rects: Vec<(u32, u32, u32, u32)> = first
+            .iter()
+            .map(|s| (s.x, s.y, s.image.width() + EXTRUDE * 2, s.image.height() + EXTRUDE * 2))
+            .collect();
+        for (i, &(x, y, w, h)) in rects.iter().enumerate() {
+            assert!(x + w <= size.0 && y + h <= size.1, "{} is outside of the sheet", first[i].name);
+            for (j, &(ox, oy, ow, oh)) in rects.iter().enumerate().skip(i + 1) {
+                let apart = x + w + PADDING <= ox || ox + ow + PADDING <= x ||
+                            y + h + PADDING <= oy || oy + oh + PADDING <= y;
+                assert!(apart, "{} overlaps {}", first[i].name, first[j].name);
+            }
+        }
+    }
+}
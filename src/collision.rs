@@ -7,223 +7,254 @@
 
 use crate::entity::*;
 use crate::world::*;
+use crate::game_state::*;
+use crate::event::*;
 use crate::math::*;
-use crate::sprite_sheet::{SpriteMask};
+use crate::sprite_sheet::SpriteMask;
+use crate::assets::{Assets, SpriteHandle};
 
-/// apply a mask to another mask, clearing any pixels that are set in both masks, when overlapped
+/// returns true if two masks overlap, when drawn at the given positions
 /// 
 /// # Arguments
 /// 
-/// * `x` - x position to apply mask
-/// * `y` - y position to apply mask
-/// * `apply_mask` - mask to apply
-/// * `mask` - mask that mask is applied to, this is side effected if masks overlap set pixels
-fn apply_mask(x: usize, y: usize, apply_mask: &SpriteMask, mask: &mut SpriteMask) {
-    for yy in 0..apply_mask.len() {
-        // don't go of the bottom of sprite
-        if y+yy >= mask.len() {
-            return;
-        }
-        else {
-            for xx in 0..apply_mask[0].len() {
-                // don't go of right edge of sprite
-                if x+xx >= mask[0].len() {
-                    break;
-                }
-                if apply_mask[yy][xx] != 0 {
-                    mask[y+yy][x+xx] = 0;   
-                }
-            }
+/// * `mask` - First mask
+/// * `position` - Position of first mask, where x is in bytes, i.e. 4 per pixel
+/// * `other` - Second mask
+/// * `other_position` - Position of second mask, where x is in bytes, i.e. 4 per pixel
+fn masks_overlap(mask: &SpriteMask, position: Point, other: &SpriteMask, other_position: Point) -> bool {
+    mask.overlaps(
+        other, 
+        (other_position.x / 4) as i32 - (position.x / 4) as i32, 
+        other_position.y as i32 - position.y as i32)
+}
+
+/// award the player a single extra life, if they have passed the bonus life score
+/// 
+/// # Arguments
+/// 
+/// * `state` - State of the game
+fn award_bonus_life(state: &mut GameState) {
+    if state.bonus_life_awarded {
+        return;
+    }
+
+    let bonus_life_score = state.bonus_life_score as i32;
+    let mut awarded = false;
+    if let Some(player) = state.get_mut_entity(state.get_player()) {
+        if player.score >= bonus_life_score {
+            player.lives_remaining += 1;
+            awarded = true;
         }
     }
+
+    if awarded {
+        state.bonus_life_awarded = true;
+        state.push_event(GameEvent::ExtraLife);
+    }
 }
 
 /// handle player or alien bullet collisions with barriers, returns true in case of collison, otherwise false
 /// 
+/// Barrier masks are checked, and a hit barrier's mask is updated, in place.
+/// 
 /// # Arguments
 ///
 /// * `is_alien`       - A boolean determing if the bullet is from an alien or player
 /// * `bounding_box`   - Bounding box of bullet
-/// * `explosion_mask` - mask applied to barrier mask, if bullet collides with any remaining pixels in barrier 
-/// *  `barriers_info` - Info related to all barriers that bullet could collide with. If a bullet collides with 
-///                      a barrier the barriers mask is updated to refect the hit, providing more of a passage 
+/// * `assets`         - Assets, for the shield bullet explosion mask
+/// * `state`          - State of the game. If a bullet collides with a barrier the barrier's mask has the shield 
+///                      bullet explosion mask cut out of it, to refect the hit, providing more of a passage 
 ///                      through.
-fn collides_with_barrier(
-    is_alien: bool,
-    bounding_box: &Rect, 
-    explosion_mask: &SpriteMask,
-    barriers_info: &mut Vec<(EntityIndex, Rect, SpriteMask)>) -> bool {
+fn collides_with_barrier(is_alien: bool, bounding_box: &Rect, assets: &Assets, state: &mut GameState) -> bool {
+
+    // find the barrier, and the pixel in its mask, the bullet hits first, if any
+    let mut hit = None;
+    for (index, barrier) in state.get_entities().iter::<Barrier>() {
+        let barrier_box = Rect::new(
+            barrier.bounding_box.origin, 
+            Size::new(barrier.bounding_box.size.width*4, barrier.bounding_box.size.height));
 
-    for i in barriers_info {        
         // first check intersection for barrier bounding box
-        if i.1.intersects(bounding_box) {
-            // now we need to dig deeper and check if the bullet intersects with the barrier sprite mask
-            let bullet_pos_x = if i.1.origin.x > bounding_box.origin.x {
-                i.1.origin.x - bounding_box.origin.x 
-            } else {
-                bounding_box.origin.x - i.1.origin.x
-            };
+        if !barrier_box.intersects(bounding_box) {
+            continue;
+        }
 
-            let barrier_height = i.1.size.height;
-            let barrier_width  = i.1.size.width>>2;  // TODO: actally address the multiple of 4 issue at its root 
-            let bullet_width   = bounding_box.size.width as usize >> 2;
-            let bpos = (bullet_pos_x as usize) >> 2;
-            let width = if bpos + bullet_width as usize > barrier_width as usize {
-                barrier_width as usize
-            }
-            else {
-                bpos + bullet_width as usize
-            };
+        // now we need to dig deeper and check if the bullet intersects with the barrier sprite mask
+        let bullet_pos_x = if barrier_box.origin.x > bounding_box.origin.x {
+            barrier_box.origin.x - bounding_box.origin.x 
+        } else {
+            bounding_box.origin.x - barrier_box.origin.x
+        };
+
+        let barrier_height = barrier_box.size.height as usize;
+        let barrier_width  = barrier_box.size.width as usize >> 2;  // TODO: actally address the multiple of 4 issue at its root 
+        let bullet_width   = bounding_box.size.width as usize >> 2;
+        let bpos = bullet_pos_x as usize >> 2;
+        let columns = bpos..(bpos + bullet_width).min(barrier_width);
+
+        let mask = &barrier.mask;
+        hit = if is_alien {
+            // handle alien bullet, which will becoming from top down
+            (0..barrier_height)
+                .find_map(|y| mask.first_in_row(y, columns.clone()).map(|x| (index, x, y, bpos)))
+        }
+        else {
+            // handle player bullet, which will becoming from bottom up, from the bottom of barrier when the 
+            // bullet hit, moving up until we find a set pixel to distory
+            (0..=barrier_height)
+                .rev()
+                .find_map(|y| mask.first_in_row(y, columns.clone()).map(|x| (index, x, y, bpos)))
+        };
+        if hit.is_some() {
+            break;
+        }
+    }
 
-            let mask = &mut i.2;
-            if is_alien {
-                // handle alien bullet, which will becoming from top down
-                for y in 0..barrier_height as usize {
-                    for x in (bullet_pos_x as usize) >> 2..width as usize {
-                        if mask[y][x] == 1 {
-                            apply_mask(x-2, y, explosion_mask, mask);
-                            return true;
-                        }
-                    }
-                }
-            }
+    let (index, x, y, bpos) = match hit {
+        Some(hit) => hit,
+        None => return false,
+    };
+
+    let explosion_mask = assets.get_shield_bullet_explosion_mask();
+    let (x, y) = if is_alien {
+        (x as i32 - 2, y as i32)
+    }
+    else {
+        // we move up to apply mask and clamp if at top of barrier mask
+        let y_clamped = 
+            if y as i32 - explosion_mask.height() as i32 > 0 {
+                (y - explosion_mask.height())+1
+            } 
             else {
-                // handle player bullet, which will becoming from bottom up
-                let bpos_y = (i.1.origin.y + i.1.size.height) - bounding_box.origin.y;
-                // iterate from bottom of barrier when the bullet hit, moving up until we find a set pixel to distory
-                for y in (0..=barrier_height as usize).rev() {
-                    for x in bpos..width { 
-                        // is barrier pixel set
-                        if mask[y][x] == 1 {
-                            // we move up to apply mask and clamp if at top of barrier mask
-                            let y_clamped = 
-                                if y as i32 - explosion_mask.len() as i32 > 0 {
-                                    (y - explosion_mask.len())+1
-                                } 
-                                else {
-                                    0
-                                };
-                            apply_mask(bpos, y_clamped, explosion_mask, mask);
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
+                0
+            };
+        (bpos as i32, y_clamped as i32)
+    };
+    if let Some(barrier) = state.get_mut_entity(index) {
+        barrier.mask.clear_masked(explosion_mask, x, y);
     }
-    false
+    true
 }
 
 /// handle any alien and player bullet collisions, with barriers, the player, and aliens
 /// 
 /// # Arguments
 /// 
-/// * `world` - The game world
-pub fn bullet_collision_system(world: &mut World) {
+/// * `assets` - Assets the game's state refers to
+/// * `state` - State of the game
+pub fn bullet_collision_system(assets: &Assets, state: &mut GameState) {
 
     // get the players position, used to check again alien bullets
     let mut player_position = Point::default(); 
     let mut player_bounding_box = Rect::default();
     let mut player_bullet_bounding_box = Rect::default();
     let mut player_bullet_in_flight = false;
-
-    if let Some(entity) = world.get_entity(world.get_player()) {
-        if let Entity::Player(player) = entity {
-            player_position = player.position;
-            player_bounding_box = player.bounding_box;
-            player_bounding_box.origin = player_position;
-
-            if player.bullet.bullet_mode == BulletMode::InFlight {
-                player_bullet_bounding_box = player.bullet.get_bounding_box();
-                player_bounding_box.origin = player.bullet.position;
-                player_bullet_bounding_box.size = 
-                    Size::new(player_bullet_bounding_box.size.width, player_bullet_bounding_box.size.height);
-                player_bullet_in_flight = true;
-            }
+    let mut player_sprite: Option<SpriteHandle> = None;
+    let mut player_bullet: Option<Bullet> = None;
+
+    if let Some(player) = state.get_entity(state.get_player()) {
+        player_position = player.position;
+        player_sprite = Some(player.sprite);
+        player_bounding_box = player.bounding_box;
+        player_bounding_box.origin = player_position;
+
+        if player.bullet.bullet_mode == BulletMode::InFlight {
+            player_bullet_bounding_box = player.bullet.get_bounding_box();
+            player_bounding_box.origin = player.bullet.position;
+            player_bullet_bounding_box.size = 
+                Size::new(player_bullet_bounding_box.size.width, player_bullet_bounding_box.size.height);
+            player_bullet_in_flight = true;
+            player_bullet = Some(player.bullet.clone());
         }
     }
 
-    // get barrier info
-    let mut barriers = vec![];
-    for index in world.get_barriers() {
-        if let Some(entity) = world.get_entity(index) {
-            if let Entity::Barrier(barrier) = entity {
-                barriers.push(
-                    (index, 
-                     Rect::new(
-                         barrier.bounding_box.origin, 
-                         Size::new(barrier.bounding_box.size.width*4, barrier.bounding_box.size.height)),
-                     barrier.mask.clone()));
-            }
+    // with pixel perfect collisions, bullets must also overlap a set pixel of what they hit, not just its bounding box
+    let pixel_perfect = state.pixel_perfect_collisions;
+    let hits_player = |bullet: &Bullet| {
+        if pixel_perfect {
+            let bounding_box = Rect::new(
+                player_position, 
+                Size::new(player_bounding_box.size.width*4, player_bounding_box.size.height));
+            bullet.get_bounding_box().intersects(&bounding_box) && 
+                player_sprite.is_some_and(|sprite| 
+                    masks_overlap(
+                        bullet.get_mask(assets), bullet.position, assets.get_sprite(sprite).get_mask(), player_position))
         }
-    }
+        else {
+            bullet.position.x >= player_position.x && 
+            bullet.position.x <= player_position.x + player_bounding_box.size.width*4 && // hmm need to fix...
+            bullet.position.y >= player_position.y && 
+            bullet.position.y <= player_position.y + player_bounding_box.size.height
+        }
+    };
+    let player_bullet_hits = |mask: &SpriteMask, position: Point| {
+        !pixel_perfect || 
+            player_bullet.as_ref().is_some_and(|bullet| 
+                masks_overlap(mask, position, bullet.get_mask(assets), bullet.position))
+    };
+
 
     let mut player_killed = false;
     let mut player_bullet_killed = false;
     let mut explosions: Vec<BulletExplosion> = vec![];
-    let bullet_explosion_sprite = world.get_alien_bullet_explosion_sprite();
-    let shield_bullet_explosion_mask = world.get_shield_bullet_explosion_mask();
-    let mut barrier_update = false;
+    let bullet_explosion_sprite = assets.get_alien_bullet_explosion_sprite();
     // handle alien bullet collisions
-    for index in world.get_alien_bullets().iter() {
-        if let Some(entity) = world.get_mut_entity(*index) {
-            if let Entity::Bullet(bullet) = entity {
-                if bullet.bullet_mode == BulletMode::InFlight {
+    for index in state.get_alien_bullets().iter() {
+        let bullet_bounding_box = match state.get_entity(*index) {
+            Some(bullet) if bullet.bullet_mode == BulletMode::InFlight => {
+                let mut bounding_box = bullet.bounding_box;
+                bounding_box.origin = bullet.position;
+                bounding_box
+            },
+            _ => continue,
+        };
+
+        // first check if collides with barrier
+        let hit_barrier = collides_with_barrier(true, &bullet_bounding_box, assets, state);
+
+        if let Some(bullet) = state.get_mut_entity(*index) {
+            if hit_barrier {
+                bullet.bullet_mode = BulletMode::Fire;
+            }
+            // check for colision with player
+            else if hits_player(bullet) {
+                bullet.bullet_mode = BulletMode::Fire;
+                player_killed = true; // handle player death below
+            }
+            // check for colision with ground
+            else if bullet.position.y + bullet.bounding_box.size.height >= World::get_ground().origin.y {  
+                bullet.bullet_mode = BulletMode::Fire;
+                explosions.push(BulletExplosion::new(
+                    bullet.position, 
+                    bullet_explosion_sprite.clone(), 
+                    state.get_bullet_explosion_time() as i32));
+            }
+            // check to see if it has hit the player's bullet
+            else if player_bullet_in_flight && bullet_bounding_box.intersects(&player_bullet_bounding_box) {
+                bullet.bullet_mode = BulletMode::Fire;
+                explosions.push(BulletExplosion::new(
+                    bullet.position, 
+                    assets.get_alien_bullet_explosiion_with_player_bullet(), 
+                    state.get_bullet_explosion_time() as i32));
+                player_bullet_killed = true;
                     
-                    // check for colision with player
-                    let mut bullet_bounding_box = bullet.bounding_box;
-                    bullet_bounding_box.origin = bullet.position;
-
-                    // first check if collides with barrier
-                    if collides_with_barrier(
-                        true,
-                        &bullet_bounding_box, 
-                        &shield_bullet_explosion_mask, 
-                        &mut barriers) {
-                        barrier_update = true;
-                        bullet.bullet_mode = BulletMode::Fire;
-                    }
-                    else if bullet.position.x >= player_position.x && 
-                       bullet.position.x <= player_position.x + player_bounding_box.size.width*4 && // hmm need to fix...
-                       bullet.position.y >= player_position.y && 
-                       bullet.position.y <= player_position.y + player_bounding_box.size.height {
-                        bullet.bullet_mode = BulletMode::Fire;
-                        player_killed = true; // handle player death below
-                    }
-                    // check for colision with ground
-                    else if bullet.position.y + bullet.bounding_box.size.height >= World::get_ground().origin.y {  
-                        bullet.bullet_mode = BulletMode::Fire;
-                        explosions.push(BulletExplosion::new(
-                            bullet.position, 
-                            bullet_explosion_sprite.clone(), 
-                            world.get_bullet_explosion_time() as i32));
-                    }
-                    // check to see if it has hit the player's bullet
-                    else if player_bullet_in_flight && bullet_bounding_box.intersects(&player_bullet_bounding_box) {
-                        bullet.bullet_mode = BulletMode::Fire;
-                        explosions.push(BulletExplosion::new(
-                            bullet.position, 
-                            world.get_alien_bullet_explosiion_with_player_bullet(), 
-                            world.get_bullet_explosion_time() as i32));
-                        player_bullet_killed = true;
-                        
-                    }
-                }
             }
         }
     } 
 
-    // now handle ufo and player bullet
+    // now handle ufo and player bullet, the points for a hit depend on the shots fired up to the hit
+    let points_if_hit = state.get_ufo_points();
     let mut ufo_points = 0;
+    let mut ufo_position = Point::new(0, 0);
     if player_bullet_in_flight && !player_bullet_killed {
-        if let Some(entity) = world.get_mut_entity(world.get_ship()) {
-            if let Entity::Ship(ship) = entity {
-                if ship.is_alive {
-                    let bounding_box = ship.get_bounding_box();
-                    if bounding_box.intersects(&player_bullet_bounding_box) {
-                        ship.is_alive = false;
-                        ufo_points = ship.points;
-                    }
+        if let Some(ship) = state.get_mut_entity(state.get_ship()) {
+            if ship.is_alive {
+                let bounding_box = ship.get_bounding_box();
+                if bounding_box.intersects(&player_bullet_bounding_box) && 
+                   player_bullet_hits(assets.get_sprite(ship.sprite).get_mask(), ship.position) {
+                    ship.is_alive = false;
+                    ufo_points = points_if_hit;
+                    ufo_position = ship.position;
                 }
             }
         }
@@ -231,176 +262,283 @@ pub fn bullet_collision_system(world: &mut World) {
 
     // if the UFO was hit by player, reset
     if ufo_points > 0 {
-        world.reset_ufo_timer();
-        world.pause_ufo();
+        state.reset_ufo_timer();
+        state.push_event(GameEvent::UfoKilled { points: ufo_points });
+
+        // add explosion, which is followed by the points awarded
+        let ufo_explosion_sprite = assets.get_ufo_explosion_sprite();
+        state.add_explosion(
+            UfoExplosion::new(
+                ufo_position,
+                ufo_explosion_sprite,
+                ufo_points,
+                state.get_ufo_explosion_time() as i32,
+                state.get_ufo_points_time() as i32));
+
         // add ufo points to players score 
-        if let Some(entity) = world.get_mut_entity(world.get_player()) {
-            if let Entity::Player(player) = entity {
-                player.score += ufo_points;
-            }
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
+            player.score += ufo_points;
         }
+        award_bonus_life(state);
     }
 
     // now handle a player death
     if player_killed || player_bullet_killed {
-        if let Some(entity) = world.get_mut_entity(world.get_player()) {
-            if let Entity::Player(player) = entity {
-                // reload bullet if killed by alien  bullet
-                if player_bullet_killed {
-                    player.bullet.bullet_mode = BulletMode::Fire;   
-                }
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
+            // reload bullet if killed by alien  bullet
+            if player_bullet_killed {
+                player.bullet.bullet_mode = BulletMode::Fire;   
+            }
 
-                // kill player if killed by alien bullet
-                if player_killed {
-                    let pos = player.position;
-                    player.position = World::player_start_position();
-                    player.lives_remaining -= 1;
-
-                    // set playing state to game over, if no lives left
-                    if player.lives_remaining == 0 {
-                        world.set_current_state(GameState::GameOver);
-                        // trigger game over timer
-                        world.reset_game_over_timer();
-                    }
-
-                    explosions.push(BulletExplosion::new(
-                        pos, 
-                        world.get_player_explosion_sprite(),
-                        world.get_bullet_explosion_time() as i32));
-
-                    // finally reset the player killed timer to delay the gameplay for a moment
-                    world.reset_player_died_timer();
-                    *world.get_mut_player_died() = true;
-
-                    // finally, play player explosion
-                    world.play_player_explosion();
+            // kill player if killed by alien bullet
+            if player_killed {
+                let pos = player.position;
+                player.position = World::player_start_position();
+                player.lives_remaining -= 1;
+
+                // set playing state to game over, if no lives left
+                if player.lives_remaining == 0 {
+                    state.mode = GameMode::GameOver;
+                    // trigger game over timer
+                    state.reset_game_over_timer();
                 }
+
+                explosions.push(BulletExplosion::new(
+                    pos, 
+                    assets.get_player_explosion_sprite(),
+                    state.get_bullet_explosion_time() as i32));
+
+                // finally reset the player killed timer to delay the gameplay for a moment
+                state.reset_player_died_timer();
+                state.player_died = true;
+
+                // finally, let everyone know the player has been killed
+                state.push_event(GameEvent::PlayerKilled);
             }
         }
     }
 
-    // add any explosions to the world
+    // add any explosions to the game
     for e in explosions {
-        world.add_explosion(Entity::BulletExplosion(e));
+        state.add_explosion(e);
     }
 
-    // player bullet collision with aliens 
-    // we probably should do a quick bounding box around all aliens, but for now just test them all
+    // player bullet collision with aliens, only those in the swarm's cells near the bullet are tested
 
     let mut bounding_box = None;
-    if let Some(entity) = world.get_mut_entity(world.get_player()) {
-        if let Entity::Player(player) = entity {
-            if player.bullet.bullet_mode == BulletMode::InFlight {
-                // first check if collides with barrier
-                if collides_with_barrier(
-                    false,
-                    &player_bullet_bounding_box, 
-                    &shield_bullet_explosion_mask,
-                    &mut barriers) {
-                    barrier_update  = true;
-                    player.bullet.bullet_mode = BulletMode::Fire;
-                }
-                else {
-                    bounding_box = Some(player.bullet.get_bounding_box());
-                }
-            }
+    if let Some(player) = state.get_mut_entity(state.get_player()) {
+        if player.bullet.bullet_mode == BulletMode::InFlight {
+            bounding_box = Some(player.bullet.get_bounding_box());
+        }
+    }
+
+    // first check if the player's bullet collides with barrier
+    if bounding_box.is_some() && collides_with_barrier(false, &player_bullet_bounding_box, assets, state) {
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
+            player.bullet.bullet_mode = BulletMode::Fire;
         }
+        bounding_box = None;
     }
 
     let mut alien_index = 0;
     let mut player_points_inc = 0;
     if let Some(bullet_bounding_box) = bounding_box {
         bounding_box = None; // assume we don't hit
-        for index in 0..world.get_number_aliens() {
-            if let Some(entity) = world.get_mut_entity(world.get_alien(index)) {
-                if let Entity::Alien(alien) = entity {
-                    if alien.is_alive {
-                        let alien_bounding_box = alien.get_bounding_box();
-                        // do they intersect ?
-                        if alien_bounding_box.intersects(&bullet_bounding_box) {
-                            // kill alien
-                            alien.is_alive = false;
-                            // set so we can update player once we have world ownership back
-                            // and add explosion
-                            bounding_box = Some(alien_bounding_box);
-                            // track alien index so we can remove it from column count
-                            alien_index = index;
-                            player_points_inc = alien.points;
-                            break;
-                        }
-                    }
-                }
+        let hit = state.get_swarm().live_cells_near(&bullet_bounding_box).find(|index| {
+            match state.get_entity(state.get_alien(*index)) {
+                // do they intersect ?
+                Some(alien) => 
+                    alien.get_bounding_box().intersects(&bullet_bounding_box) && 
+                    player_bullet_hits(alien.animation.get_mask(assets), alien.position),
+                None => false,
             }
-        }
-    }
-
-    // update barrier mask if bullet collided
-    if barrier_update {
-        for i in barriers {
-            if let Some(entity) = world.get_mut_entity(i.0) {
-                if let Entity::Barrier(barrier) = entity {
-                    barrier.mask = i.2;
-                }
+        });
+
+        if let Some(index) = hit {
+            if let Some(alien) = state.get_mut_entity(state.get_alien(index)) {
+                // alien explodes, it is only removed from the swarm once this has finished
+                alien.state = AlienState::Exploding;
+                // set so we can update player once we have state ownership back
+                bounding_box = Some(alien.get_bounding_box());
+                // track alien index so we can remove it from the swarm
+                alien_index = index;
+                player_points_inc = alien.points;
             }
         }
     }
-
     
     //if update_player_bullet {
     if let Some(alien_bounding_box) = bounding_box {
 
-        // add explosion
-        let bullet_explosion_sprite = world.get_player_alien_bullet_explosion_sprite();
-        world.add_explosion(
-            Entity::BulletExplosion(
-                BulletExplosion::new(
-                    alien_bounding_box.origin, 
-                    bullet_explosion_sprite, 
-                    world.get_bullet_explosion_time() as i32)));
-
-        // remove alien from column index, needed to keep track of most left and right column
-        // and update swarm speed... this is a bit of hack to emulate the feel of the original space invaders. seems
-        // to feel about OK, but it is not an emulation of the orignal game, that relied on how the each alien were 
-        // rendered (one per interrupt), thus speeding up naturally as more were killed!
-        world.kill_alien(alien_index);
-        //*world.get_mut_alien_swarm_speed() -= Duration::from_millis(9);
+        // only one alien explodes at a time, so if one is still exploding it is removed straight away
+        remove_exploded_alien(state);
+        state.start_alien_explosion(alien_index);
+
+        let mut updated_score = 0;
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
+            player.bullet.bullet_mode = BulletMode::Fire;
+            // add points to players score
+            player.score += player_points_inc;
+            updated_score = player.score;
+        }
+
+        state.push_event(GameEvent::AlienKilled { 
+            row: state.get_alien_row(alien_index), 
+            x: alien_bounding_box.center().x });
+        award_bonus_life(state);
 
+        // check high-score and update, if necessary
+        if state.high_score < updated_score as u32 {
+            state.high_score = updated_score as u32;
+        }
+    }
+}
+
+/// remove the exploding alien, if any, from the swarm once its explosion has finished
+/// 
+/// # Arguments
+/// 
+/// * `state` - State of the game
+pub fn remove_exploded_alien(state: &mut GameState) {
+    let alien_index = match state.exploding_alien {
+        Some(index) => index,
+        None => return,
+    };
+    state.end_alien_explosion();
+
+    if let Some(alien) = state.get_mut_entity(state.get_alien(alien_index)) {
+        alien.state = AlienState::Dead;
+    }
+
+    // remove alien from column index, needed to keep track of most left and right column
+    // and update swarm speed... this is a bit of hack to emulate the feel of the original space invaders. seems
+    // to feel about OK, but it is not an emulation of the orignal game, that relied on how the each alien were 
+    // rendered (one per interrupt), thus speeding up naturally as more were killed!
+    state.kill_alien();
+
+    // faithful swarm movement speeds up on its own, as fewer aliens are moved each pass
+    if state.swarm_movement == SwarmMovement::Timed {
         // TODO: fixup the magic numbers below!
-        if world.get_alien_dead() == World::number_aliens()-1 {
-            *world.get_mut_alien_speed() += 10;
+        if state.alien_dead == state.get_number_aliens() as i32 - 1 {
+            state.alien_speed += 10;
         }
-        else if world.get_alien_dead() % 4 == 0 {
-            //*world.get_mut_alien_swarm_speed() -= Duration::from_millis(9);
-            *world.get_mut_alien_speed() += 3;
+        else if state.alien_dead % 4 == 0 {
+            state.alien_speed += 3;
         }
-        
-        // check if music tempo should be increased
-        if world.get_alien_dead() % world.get_sound().number_of_music_variants() as i32 == 0 {
-            world.set_current_bpm(world.get_current_bpm()+1);
+    }
+    
+    // should move to next level?
+    if state.mode == GameMode::Playing && state.alien_dead == state.get_number_aliens() as i32 {
+        state.mode = GameMode::NextLevel;
+        state.reset_next_level_timer();
+        state.push_event(GameEvent::LevelCleared);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::controls::ship_control_system;
+    use crate::game_state::tests::new_game_state;
+
+    /// put the player's bullet in flight at a position
+    fn fire_player_bullet(state: &mut GameState, position: Point) {
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
+            player.bullet.bullet_mode = BulletMode::InFlight;
+            player.bullet.position = position;
         }
+    }
 
-        let mut updated_score = 0;
-        if let Some(entity) = world.get_mut_entity(world.get_player()) {
-            if let Entity::Player(player) = entity {
-                player.bullet.bullet_mode = BulletMode::Fire;
-                // add points to players score
-                player.score += player_points_inc;
-                updated_score = player.score;
-            }
-        }
+    /// returns the player's score
+    fn score(state: &GameState) -> u32 {
+        state.get_entity(state.get_player()).map_or(0, |player| player.score as u32)
+    }
 
-        world.play_alien_explosion();
+    /// set the player's score, and award a life if it has reached the bonus score, returning the player's lives
+    fn score_and_award(state: &mut GameState, score: i32) -> i32 {
+        state.get_mut_entity(state.get_player()).unwrap().score = score;
+        state.update();
+        award_bonus_life(state);
+        state.get_entity(state.get_player()).unwrap().lives_remaining
+    }
 
-        // check high-score and update, if necessary
-        if world.get_high_score() < updated_score as u32 {
-            *world.get_mut_high_score() = updated_score as u32;
-        }
+    #[test]
+    fn extra_life_awarded_once_at_threshold() {
+        let (assets, mut state) = new_game_state();
+        state.bonus_life_score = 1000;
+        let lives = state.get_entity(state.get_player()).unwrap().lives_remaining;
+        let extra_life = |state: &GameState| state.get_events().any(|event| event == GameEvent::ExtraLife);
+
+        assert_eq!(score_and_award(&mut state, 990), lives);
+        assert!(!extra_life(&state));
+        assert_eq!(score_and_award(&mut state, 1000), lives + 1);
+        assert!(extra_life(&state));
+
+        // only ever once a game
+        assert_eq!(score_and_award(&mut state, 1500), lives + 1);
+        assert!(!extra_life(&state));
+        assert_eq!(score_and_award(&mut state, 2000), lives + 1);
+
+        // a new game can award it again
+        new_game(&assets, &mut state);
+        assert_eq!(score_and_award(&mut state, 1000), lives + 1);
+        assert!(extra_life(&state));
+    }
 
-        // should move to next level?
-        if world.get_current_state() == GameState::Playing && world.get_alien_dead() == World::number_aliens() {
-            world.set_current_state(GameState::NextLevel);
-            world.reset_next_level_timer();
-            world.pause_ufo();
+    #[test]
+    fn ufo_points_go_by_shots_fired_when_hit() {
+        let (assets, mut state) = new_game_state();
+        state.arcade_ufo = true;
+        state.reset_ufo_timer();
+
+        // the UFO appears before any shots are fired
+        state.advance_time(Duration::from_secs(60));
+        ship_control_system(&mut state);
+        let ship = state.get_entity(state.get_ship()).unwrap().clone();
+        assert!(ship.is_alive);
+
+        // shots fired while it crosses the screen still count
+        state.player_shots = 8;
+        fire_player_bullet(&mut state, ship.position + Vector::new(8, 2));
+        bullet_collision_system(&assets, &mut state);
+
+        assert!(!state.get_entity(state.get_ship()).unwrap().is_alive);
+        assert_eq!(score(&state), 300);
+        assert!(state.get_events().any(|event| event == GameEvent::UfoKilled { points: 300 }));
+    }
+
+    #[test]
+    fn pixel_perfect_bullet_passes_beside_antenna() {
+        let (assets, state) = new_game_state();
+        let cell = state.get_swarm().cell(0, 0);
+        let alien = state.get_entity(state.get_alien(cell)).unwrap().clone();
+        let alien_mask = alien.animation.get_mask(&assets);
+        let bullet = state.get_entity(state.get_player()).unwrap().bullet.clone();
+        let bullet_mask = bullet.get_mask(&assets);
+
+        // along the top of the alien's box, with just the tip of the bullet inside it, positions where the
+        // bullet only touches the box, and where it touches the antenna
+        let y = alien.position.y + 2 - bullet.bounding_box.size.height;
+        let box_hits: Vec<(Point, bool)> = (0..alien.bounding_box.size.width)
+            .map(|x| Point::new(alien.position.x + x * 4, y))
+            .filter(|position| {
+                let bounding_box = Rect::new(*position, bullet.get_bounding_box().size);
+                alien.get_bounding_box().intersects(&bounding_box)
+            })
+            .map(|position| (position, masks_overlap(alien_mask, alien.position, bullet_mask, position)))
+            .collect();
+        let miss = box_hits.iter().find(|(_, pixel_hit)| !pixel_hit).expect("bullet beside the antenna").0;
+        let antenna = box_hits.iter().find(|(_, pixel_hit)| *pixel_hit).expect("bullet on the antenna").0;
+
+        for (position, pixel_perfect, hit) in [(miss, false, true), (miss, true, false), (antenna, true, true)] {
+            let (assets, mut state) = new_game_state();
+            state.pixel_perfect_collisions = pixel_perfect;
+            fire_player_bullet(&mut state, position);
+            bullet_collision_system(&assets, &mut state);
+
+            let alien = state.get_entity(state.get_alien(cell)).unwrap();
+            assert_eq!(alien.state == AlienState::Exploding, hit, "at {:?} pixel perfect {}", position, pixel_perfect);
+            assert_eq!(score(&state) > 0, hit);
         }
     }
-}
\ No newline at end of file
+}
@@ -1,18 +1,103 @@
 //! Description: 
 //! 
-//! Very simple config file support, currenlty only used for high score.
+//! Very simple config file support, currenlty used for high score and the cabinet style (DIP switch)
+//! settings edited from the operator settings screen. Along with options given on the command line.
 //! 
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 
 use confy;
 
+use crate::world::SwarmMovement;
+use crate::entity::PLAYER_START_LIVES;
+
+/// fewest lives a game can be started with, as per the original DIP switches
+pub const MIN_STARTING_LIVES: u32 = 3;
+/// most lives a game can be started with, as per the original DIP switches
+pub const MAX_STARTING_LIVES: u32 = 6;
+/// score at which the original awarded an extra life
+pub const DEFAULT_BONUS_LIFE_SCORE: u32 = 1500;
+/// scores at which an extra life can be awarded, as per the original DIP switches
+pub const BONUS_LIFE_SCORES: [u32; 2] = [1000, 1500];
+/// sound pack played, if not set in the configuration file, relative to the asset root
+pub const DEFAULT_SOUND_PACK: &str = "sound-pack.json";
+/// default sound pack, before it was relative to the asset root
+const OLD_DEFAULT_SOUND_PACK: &str = "./assets/sound-pack.json";
+
+/// Number of coins needed for a number of credits
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Coinage {
+    #[default]
+    OneCoinOneCredit,
+    OneCoinTwoCredits,
+    TwoCoinsOneCredit,
+}
+
+impl Coinage {
+    /// given one coinage, return the next
+    pub fn next(&self) -> Self {
+        match self {
+            Coinage::OneCoinOneCredit => Coinage::OneCoinTwoCredits,
+            Coinage::OneCoinTwoCredits => Coinage::TwoCoinsOneCredit,
+            Coinage::TwoCoinsOneCredit => Coinage::OneCoinOneCredit,
+        }
+    }
+
+    /// returns the number of coins needed
+    pub fn coins(&self) -> u32 {
+        match self {
+            Coinage::TwoCoinsOneCredit => 2,
+            _ => 1,
+        }
+    }
+
+    /// returns the number of credits awarded, once enough coins have been inserted
+    pub fn credits(&self) -> u32 {
+        match self {
+            Coinage::OneCoinTwoCredits => 2,
+            _ => 1,
+        }
+    }
+}
+
 /// Configuration structure for space invaders, that is stored persistently (externally)
-#[derive(Serialize, Deserialize)]
+/// 
+/// Any values missing from an existing configuration file are taken from `Default`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     /// version number
     version: String,
     /// most recent highscore
     high_score: u32,
+    /// how the alien swarm is moved
+    swarm_movement: SwarmMovement,
+    /// number of lives at the start of a game [3..6]
+    starting_lives: u32,
+    /// score at which a single extra life is awarded, one of BONUS_LIFE_SCORES
+    bonus_life_score: u32,
+    /// coins needed for credits
+    coinage: Coinage,
+    /// can a game be started without a credit
+    free_play: bool,
+    /// do aliens reload their bullets faster as the player's score increases, as in the original
+    arcade_alien_reload: bool,
+    /// does the UFO follow the original's timing and scoring
+    arcade_ufo: bool,
+    /// bullets only hit aliens, the UFO, and the player where their sprites' pixels overlap, rather than their 
+    /// bounding boxes
+    pixel_perfect_collisions: bool,
+    /// synthesise sound effects and march, rather than playing samples from assets/sounds
+    synth_sound: bool,
+    /// JSON manifest of the sound pack played, when not synthesising sound
+    sound_pack: String,
+    /// play sound effects centred, rather than panned to where they happen
+    mono_sound: bool,
+    /// level of all sound [0..1]
+    master_volume: f32,
+    /// level of march [0..1]
+    music_volume: f32,
+    /// level of sound effects [0..1]
+    effects_volume: f32,
 }
 
 /// `Config` implements `Default`
@@ -21,6 +106,20 @@ impl ::std::default::Default for Config {
         Self { 
             version: "0.1".into(), 
             high_score: 0, 
+            swarm_movement: SwarmMovement::default(),
+            starting_lives: PLAYER_START_LIVES as u32,
+            bonus_life_score: DEFAULT_BONUS_LIFE_SCORE,
+            coinage: Coinage::default(),
+            free_play: true,
+            arcade_alien_reload: false,
+            arcade_ufo: false,
+            pixel_perfect_collisions: false,
+            synth_sound: false,
+            sound_pack: DEFAULT_SOUND_PACK.into(),
+            mono_sound: false,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            effects_volume: 1.0,
         } 
     }
 }
@@ -28,7 +127,27 @@ impl ::std::default::Default for Config {
 impl Config {
     /// Create (load) configutation 
     pub fn new() -> Self {
-        confy::load("space-invaders").unwrap()
+        let mut config: Config = confy::load("space-invaders").unwrap();
+        config.validate();
+        config
+    }
+
+    /// replace any invalid settings, for example from a hand edited configuration file, with their defaults
+    pub fn validate(&mut self) {
+        if !self.set_starting_lives(self.starting_lives) {
+            log::warn!("invalid starting lives {} in configuration, using {}", self.starting_lives, PLAYER_START_LIVES);
+            self.starting_lives = PLAYER_START_LIVES as u32;
+        }
+        if !self.set_bonus_life_score(self.bonus_life_score) {
+            log::warn!(
+                "invalid bonus life score {} in configuration, using {}",
+                self.bonus_life_score,
+                DEFAULT_BONUS_LIFE_SCORE);
+            self.bonus_life_score = DEFAULT_BONUS_LIFE_SCORE;
+        }
+        if self.sound_pack == OLD_DEFAULT_SOUND_PACK {
+            self.sound_pack = DEFAULT_SOUND_PACK.into();
+        }
     }
 
     /// update highscore (does not store externally)
@@ -45,6 +164,138 @@ impl Config {
         self.high_score
     }
 
+    /// returns how the alien swarm is moved
+    pub fn get_swarm_movement(&self) -> SwarmMovement {
+        self.swarm_movement
+    }
+
+    /// set how the alien swarm is moved (does not store externally)
+    pub fn set_swarm_movement(&mut self, swarm_movement: SwarmMovement) {
+        self.swarm_movement = swarm_movement;
+    }
+
+    /// returns the number of lives at the start of a game, clamped to [MIN_STARTING_LIVES,MAX_STARTING_LIVES]
+    pub fn get_starting_lives(&self) -> u32 {
+        self.starting_lives.clamp(MIN_STARTING_LIVES, MAX_STARTING_LIVES)
+    }
+
+    /// set the number of lives at the start of a game (does not store externally)
+    ///
+    /// returns false, leaving the setting unchanged, if lives is not within [MIN_STARTING_LIVES,MAX_STARTING_LIVES]
+    pub fn set_starting_lives(&mut self, lives: u32) -> bool {
+        if (MIN_STARTING_LIVES..=MAX_STARTING_LIVES).contains(&lives) {
+            self.starting_lives = lives;
+            return true;
+        }
+        false
+    }
+
+    /// returns the score at which a single extra life is awarded
+    pub fn get_bonus_life_score(&self) -> u32 {
+        self.bonus_life_score
+    }
+
+    /// set the score at which a single extra life is awarded (does not store externally)
+    ///
+    /// returns false, leaving the setting unchanged, if score is not one of BONUS_LIFE_SCORES
+    pub fn set_bonus_life_score(&mut self, score: u32) -> bool {
+        if BONUS_LIFE_SCORES.contains(&score) {
+            self.bonus_life_score = score;
+            return true;
+        }
+        false
+    }
+
+    /// returns the coins needed for credits
+    pub fn get_coinage(&self) -> Coinage {
+        self.coinage
+    }
+
+    /// set the coins needed for credits (does not store externally)
+    pub fn set_coinage(&mut self, coinage: Coinage) {
+        self.coinage = coinage;
+    }
+
+    /// returns true if a game can be started without a credit
+    pub fn get_free_play(&self) -> bool {
+        self.free_play
+    }
+
+    /// set if a game can be started without a credit (does not store externally)
+    pub fn set_free_play(&mut self, free_play: bool) {
+        self.free_play = free_play;
+    }
+
+    /// returns true if aliens reload their bullets as in the original
+    pub fn get_arcade_alien_reload(&self) -> bool {
+        self.arcade_alien_reload
+    }
+
+    /// set if aliens reload their bullets as in the original (does not store externally)
+    pub fn set_arcade_alien_reload(&mut self, arcade_alien_reload: bool) {
+        self.arcade_alien_reload = arcade_alien_reload;
+    }
+
+    /// returns true if the UFO follows the original's timing and scoring
+    pub fn get_arcade_ufo(&self) -> bool {
+        self.arcade_ufo
+    }
+
+    /// set if the UFO follows the original's timing and scoring (does not store externally)
+    pub fn set_arcade_ufo(&mut self, arcade_ufo: bool) {
+        self.arcade_ufo = arcade_ufo;
+    }
+
+    /// returns true if bullets only hit where their sprites' pixels overlap, rather than their bounding boxes
+    pub fn get_pixel_perfect_collisions(&self) -> bool {
+        self.pixel_perfect_collisions
+    }
+
+    /// returns true if sound effects and march are synthesised, only read when the game starts
+    pub fn get_synth_sound(&self) -> bool {
+        self.synth_sound
+    }
+
+    /// returns the JSON manifest of the sound pack played, when not synthesising sound
+    pub fn get_sound_pack(&self) -> &str {
+        &self.sound_pack
+    }
+
+    /// returns true if sound effects are played centred, rather than panned to where they happen
+    pub fn get_mono_sound(&self) -> bool {
+        self.mono_sound
+    }
+
+    /// returns the level of all sound, clamped to [0,1]
+    pub fn get_master_volume(&self) -> f32 {
+        self.master_volume.clamp(0.0, 1.0)
+    }
+
+    /// set the level of all sound, clamped to [0,1] (does not store externally)
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// returns the level of the march, clamped to [0,1]
+    pub fn get_music_volume(&self) -> f32 {
+        self.music_volume.clamp(0.0, 1.0)
+    }
+
+    /// set the level of the march, clamped to [0,1] (does not store externally)
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.music_volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// returns the level of sound effects, clamped to [0,1]
+    pub fn get_effects_volume(&self) -> f32 {
+        self.effects_volume.clamp(0.0, 1.0)
+    }
+
+    /// set the level of sound effects, clamped to [0,1] (does not store externally)
+    pub fn set_effects_volume(&mut self, volume: f32) {
+        self.effects_volume = volume.clamp(0.0, 1.0);
+    }
+
     /// returns the version number from configuration
     pub fn get_version(&self) -> String {
         self.version.clone()
@@ -54,4 +305,73 @@ impl Config {
     pub fn store(&self) {
         confy::store("space-invaders", self).unwrap();
     }
-}
\ No newline at end of file
+}
+
+/// check that each of the given command line options, if it is given, has a value
+/// 
+/// # Arguments
+/// 
+/// * `options` - Names of options that take a value, including leading dashes
+pub fn check_command_line(options: &[&str]) -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    for (i, arg) in args.iter().enumerate() {
+        if options.contains(&arg.as_str()) && args.get(i + 1).is_none_or(|value| value.starts_with("--")) {
+            return Err(format!("{} requires a value", arg));
+        }
+    }
+    Ok(())
+}
+
+/// returns the value of a command line option, given either as `--option value` or `--option=value`
+/// 
+/// # Arguments
+/// 
+/// * `option` - Name of option, including leading dashes
+pub fn command_line_option(option: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == option {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(option).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let mut config = Config::default();
+
+        assert!(!config.set_starting_lives(MIN_STARTING_LIVES - 1));
+        assert!(!config.set_starting_lives(MAX_STARTING_LIVES + 1));
+        assert_eq!(config.get_starting_lives(), PLAYER_START_LIVES as u32);
+        assert!(config.set_starting_lives(MAX_STARTING_LIVES));
+        assert_eq!(config.get_starting_lives(), MAX_STARTING_LIVES);
+
+        assert!(!config.set_bonus_life_score(1234));
+        assert_eq!(config.get_bonus_life_score(), DEFAULT_BONUS_LIFE_SCORE);
+        assert!(config.set_bonus_life_score(1000));
+        assert_eq!(config.get_bonus_life_score(), 1000);
+    }
+
+    #[test]
+    fn validate_replaces_invalid_settings_with_defaults() {
+        // e.g. from a hand edited configuration file
+        let mut config: Config = serde_json::from_str(
+            r#"{ "starting_lives": 99, "bonus_life_score": 7, "sound_pack": "./assets/sound-pack.json", "high_score": 120 }"#)
+            .unwrap();
+        config.validate();
+
+        assert_eq!(config.get_starting_lives(), PLAYER_START_LIVES as u32);
+        assert_eq!(config.get_bonus_life_score(), DEFAULT_BONUS_LIFE_SCORE);
+        assert_eq!(config.get_sound_pack(), DEFAULT_SOUND_PACK);
+        // valid settings are kept
+        assert_eq!(config.get_high_score(), 120);
+    }
+}
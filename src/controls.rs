@@ -9,7 +9,11 @@ use either::*;
 
 use crate::entity::*;
 use crate::world::*;
+use crate::game_state::*;
+use crate::assets::Assets;
+use crate::event::*;
 use crate::math::*;
+use crate::collision::remove_exploded_alien;
 
 /// Player control inputs.
 #[derive(Debug)]
@@ -18,6 +22,25 @@ pub struct Controls {
     pub direction: Direction,
     /// Shoot the cannon.
     pub fire: bool,
+    /// Enter or leave the operator settings screen.
+    pub service: bool,
+    /// Insert a coin.
+    pub coin: bool,
+    /// Navigate menus, such as the operator settings screen.
+    pub menu: MenuControls,
+    /// Toggle mute for all sound.
+    pub mute: bool,
+    /// Toggle mute for the march.
+    pub mute_music: bool,
+}
+
+/// Menu navigation inputs, unlike player movement these are only set when a key is first pressed.
+#[derive(Debug, Default)]
+pub struct MenuControls {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
 }
 
 /// The player can only move left or right, but can also be stationary.
@@ -36,6 +59,11 @@ impl Default for Controls {
         Controls {
             direction: Direction::default(),
             fire: false,
+            service: false,
+            coin: false,
+            menu: MenuControls::default(),
+            mute: false,
+            mute_music: false,
         }
     }
 }
@@ -47,13 +75,13 @@ impl Default for Direction {
 }
 
 // player control system, control movement of player and firing
-pub fn player_control_system(world: &mut World, controls: Option<Controls>) {
+pub fn player_control_system(assets: &Assets, state: &mut GameState, controls: Option<Controls>) {
 
     // is the player in the process of dying (we assume that they still have lives, checked elsewhere)
-    if world.get_player_died() {
+    if state.player_died {
         // if timer has expired next player life is respawned and game continues
-        if world.has_player_died_timer_expired() {
-            *world.get_mut_player_died() = false;
+        if state.has_player_died_timer_expired() {
+            state.player_died = false;
         }
         // otherwise no control updates happen for player
         else {
@@ -70,75 +98,72 @@ pub fn player_control_system(world: &mut World, controls: Option<Controls>) {
             Direction::Still => 0
         };
 
-        let bounds = world.get_bounds();
-        let bullet_speed = world.get_player_bullet_speed();
+        let bounds = state.get_bounds();
+        let bullet_speed = state.get_player_bullet_speed();
         let mut bullet_explosion = None;
 
-        let mut fire_sound = false;
-        if let Some(entity) = world.get_mut_entity(world.get_player()) {
-            if let Entity::Player(player) = entity {
+        let mut fire_sound = None;
+        let mut player_shots = state.player_shots;
+        if let Some(player) = state.get_mut_entity(state.get_player()) {
 
-                if player.bullet.bullet_mode == BulletMode::Fire {
-                    // player pressed fire and a they do not already have a bullet in play, then generate one
-                    // we do this before updating the player's movement...
-                    if controls.fire {
-                        player.bullet.bullet_mode = BulletMode::InFlight;
+            if player.bullet.bullet_mode == BulletMode::Fire {
+                // player pressed fire and a they do not already have a bullet in play, then generate one
+                // we do this before updating the player's movement...
+                if controls.fire {
+                    player.bullet.bullet_mode = BulletMode::InFlight;
 
-                        player.bullet.position = Point::new(
-                            player.position.x + player.bounding_box.width()/2 + 30,
-                            ((player.position.y as i32) - player.bullet.bounding_box.height() as i32) as u32);
+                    player.bullet.position = Point::new(
+                        player.position.x + player.bounding_box.width()/2 + 30,
+                        ((player.position.y as i32) - player.bullet.bounding_box.height() as i32) as u32);
                         
-                        fire_sound = true;
-                    }
+                    fire_sound = Some(player.bullet.position.x);
+                    player_shots += 1;
                 }
-                // animate player bullet if play 
-                else {
-                    player.bullet.position.y = (player.bullet.position.y as i32 - bullet_speed) as u32;
-                    if player.bullet.position.y <= bounds.min_y() {
-                        bullet_explosion = Some(player.bullet.position);
-                        player.bullet.bullet_mode = BulletMode::Fire;
-                    }
+            }
+            // animate player bullet if play 
+            else {
+                player.bullet.position.y = (player.bullet.position.y as i32 - bullet_speed) as u32;
+                if player.bullet.position.y <= bounds.min_y() {
+                    bullet_explosion = Some(player.bullet.position);
+                    player.bullet.bullet_mode = BulletMode::Fire;
                 }
+            }
 
-                let x = (player.position.x as i32 + movement) as u32;
-                if x >= bounds.min_x() && x+player.sprite.width*4 <= bounds.max_x() {
-                    player.position = Point::new(x, player.position.y)
-                }
+            let x = (player.position.x as i32 + movement) as u32;
+            if x >= bounds.min_x() && x+assets.get_sprite(player.sprite).width*4 <= bounds.max_x() {
+                player.position = Point::new(x, player.position.y)
             }
         }
 
-        // now that we have the world back we can add a bullet explosion, if necessary
+        state.player_shots = player_shots;
+
+        // now that we have the state back we can add a bullet explosion, if necessary
         if let Some(position) = bullet_explosion {
-            let bullet_explosion_sprite = world.get_player_bullet_explosion_sprite();
-            world.add_explosion(
-                Entity::BulletExplosion(
-                    BulletExplosion::new(
-                        position, 
-                        bullet_explosion_sprite, 
-                        world.get_bullet_explosion_time() as i32)));
+            let bullet_explosion_sprite = assets.get_player_bullet_explosion_sprite();
+            state.add_explosion(
+                BulletExplosion::new(
+                    position, 
+                    bullet_explosion_sprite, 
+                    state.get_bullet_explosion_time() as i32));
         }
 
-        // finally if we need to play the player fire sound, then do
-        if fire_sound {
-            world.play_player_shot();
+        // finally if the player fired, then let everyone know
+        if let Some(x) = fire_sound {
+            state.push_event(GameEvent::ShotFired { x });
         }
     }
 }
 
 // handle bullet control, this can mean moving alien bullets, player bullet movement is handled by
 // the player, and all explosions
-pub fn bullet_control_system(world: &mut World) {
+pub fn bullet_control_system(state: &mut GameState) {
 
     // is the player in the process of dying, then no updates take place, except for any explosion animations
-    if world.get_player_died() {
+    if state.player_died {
         // step any bullet explosions
-        for index in 0..world.number_explosions() {
-            if let Some(entity) = world.get_mut_entity(world.get_explosion_index(index)) {
-                if let Entity::BulletExplosion(explosion) = entity {
-                    if let Right(animation) = &mut explosion.sprite {
-                        animation.step();
-                    }
-                }
+        for (_, explosion) in state.get_mut_entities().iter_mut::<BulletExplosion>() {
+            if let Right(animation) = &mut explosion.sprite {
+                animation.step();
             }
         }
         return;
@@ -146,63 +171,57 @@ pub fn bullet_control_system(world: &mut World) {
 
     // get the players x position, used for targeted bullet
     let mut player_x_position = 0; 
-    if let Some(entity) = world.get_mut_entity(world.get_player()) {
-        if let Entity::Player(player) = entity {
-            player_x_position = player.position.x;
-        }
+    if let Some(player) = state.get_mut_entity(state.get_player()) {
+        player_x_position = player.position.x;
     }
 
     // first we step any alien bullets that are in flight
-    if world.has_animate_alien_bullet_timer_expired() {
-        for index in world.get_alien_bullets().iter() {
-            if let Some(entity) = world.get_mut_entity(*index) {
-                if let Entity::Bullet(bullet) = entity {
-                    if bullet.bullet_mode == BulletMode::InFlight {
-                        bullet.position.y = 
-                            (bullet.position.y as i32 + World::get_alien_bullet_initial_speed() as i32) as u32;
-                        if let Right(animation) = &mut bullet.sprite {
-                            animation.step();
-                        }
+    if state.has_animate_alien_bullet_timer_expired() {
+        for index in state.get_alien_bullets().iter() {
+            if let Some(bullet) = state.get_mut_entity(*index) {
+                if bullet.bullet_mode == BulletMode::InFlight {
+                    bullet.position.y = 
+                        (bullet.position.y as i32 + World::get_alien_bullet_initial_speed() as i32) as u32;
+                    if let Right(animation) = &mut bullet.sprite {
+                        animation.step();
                     }
                 }
             }
         }
 
-        world.reset_animate_alien_bullet_timer();
+        state.reset_animate_alien_bullet_timer();
     }
 
     // add an alien bullet?
-    if world.has_alien_bullet_timer_expired() {
+    if state.has_alien_bullet_timer_expired() {
         // what type of bullet should we create?
-        let next = world.get_next_alien_bullet_type();
+        let next = state.next_alien_bullet_type;
 
         // first get the position of alien that will drop bullet
         let mut alien_position = Point::new(player_x_position,0);
         match next {
             AlienBulletType::Rolling => {
                 // find alien that is closet to the player
-                for column in 0..world.get_number_alien_columns() {
-                    if let Some(index) = world.lowest_alive_alien_in_column(column) {
-                        if let Some(entity) = world.get_mut_entity(index) {
-                            if let Entity::Alien(alien) = entity {
-                                if player_x_position < alien.position.x {
-                                    // handle the case when player is to the left of aliens
-                                    //if column == 0 {
-                                        alien_position = alien.position;
-                                    //}
-                                    // centre the bullet
-                                    alien_position.x += alien.bounding_box.size.width ;
-                                    alien_position.y += alien.bounding_box.size.height ;
-                                    break;
-                                }
-                                alien_position = alien.position;
-                                if player_x_position*4 >= alien.position.x && 
-                                   player_x_position*4 <= alien.position.x + alien.bounding_box.size.width {
-                                    // centre the bullet
-                                    alien_position.x += alien.bounding_box.size.width;
-                                    alien_position.y += alien.bounding_box.size.height;
-                                    break;
-                                }
+                for column in 0..state.get_number_alien_columns() {
+                    if let Some(index) = state.lowest_alive_alien_in_column(column) {
+                        if let Some(alien) = state.get_mut_entity(index) {
+                            if player_x_position < alien.position.x {
+                                // handle the case when player is to the left of aliens
+                                //if column == 0 {
+                                    alien_position = alien.position;
+                                //}
+                                // centre the bullet
+                                alien_position.x += alien.bounding_box.size.width ;
+                                alien_position.y += alien.bounding_box.size.height ;
+                                break;
+                            }
+                            alien_position = alien.position;
+                            if player_x_position*4 >= alien.position.x && 
+                               player_x_position*4 <= alien.position.x + alien.bounding_box.size.width {
+                                // centre the bullet
+                                alien_position.x += alien.bounding_box.size.width;
+                                alien_position.y += alien.bounding_box.size.height;
+                                break;
                             }
                         }       
                     }
@@ -215,46 +234,30 @@ pub fn bullet_control_system(world: &mut World) {
                 // if ufo is active then have it fire if plunger 
                 let mut ufo_bullet = false;
                 if next == AlienBulletType::Plunger {
-                    if let Some(entity) = world.get_mut_entity(world.get_ship()) {
-                        if let Entity::Ship(ship) = entity {
-                            if ship.is_alive {
-                                ufo_bullet = true;
-                                alien_position = ship.position;
-                                alien_position.x += ship.bounding_box.size.width / 2 ;
-                                alien_position.y += ship.bounding_box.size.height / 2;
-                            }
+                    if let Some(ship) = state.get_mut_entity(state.get_ship()) {
+                        if ship.is_alive {
+                            ufo_bullet = true;
+                            alien_position = ship.position;
+                            alien_position.x += ship.bounding_box.size.width / 2 ;
+                            alien_position.y += ship.bounding_box.size.height / 2;
                         }
                     }
                 }
 
                 if !ufo_bullet {
-                    // loop until we find a column with an alien
-                    // NOTE: this only terminates if NOT ALL aliens are dead, make sure this is not the case :-)
-                    loop {
-                        let column = world.gen_rand_column();
-                        if let Some(index) = world.lowest_alive_alien_in_column(column) {
-                            if let Some(entity) = world.get_mut_entity(index) {
-                                if let Entity::Alien(alien) = entity {
-                                    alien_position = alien.position;
-                                    alien_position.x += alien.bounding_box.size.width ;
-                                    alien_position.y += alien.bounding_box.size.height ;
-                                    break;
-                                }
-                            }
-                        }
+                    // pick a random column with a live alien, its lowest alien fires
+                    let index = state.gen_rand_live_column().and_then(|column| state.lowest_alive_alien_in_column(column));
+                    if let Some(alien) = index.and_then(|index| state.get_entity(index)) {
+                        alien_position = alien.position;
+                        alien_position.x += alien.bounding_box.size.width ;
+                        alien_position.y += alien.bounding_box.size.height ;
                     }
                 }
             },
         };
 
         // find the entity for our bullet
-        let entity = match next {
-            AlienBulletType::Rolling => world.get_mut_entity(world.get_alien_rolling()),
-            AlienBulletType::Plunger => world.get_mut_entity(world.get_alien_plunger()),
-            AlienBulletType::Squiggly => world.get_mut_entity(world.get_alien_squiggly()),
-        };
-
-        if let Some(Entity::Bullet(bullet)) = entity {
+        if let Some(bullet) = state.get_mut_entity(state.get_alien_bullet(next)) {
             // bullet must be in fire mode to fire
             if bullet.bullet_mode == BulletMode::Fire {
                 // tracking bullet?
@@ -265,65 +268,88 @@ pub fn bullet_control_system(world: &mut World) {
         }
 
         // next time we do a different bullet type
-        world.inc_next_bullet_type();
+        state.inc_next_bullet_type();
 
         // finally reset timer so we can add another bullet
-        world.reset_alien_bullet_timer();
+        state.reset_alien_bullet_timer();
     }
 
-    let mut indexes = vec![];
-    for index in 0..world.number_explosions() {
-        if let Some(entity) = world.get_mut_entity(world.get_explosion_index(index)) {
-            if let Entity::BulletExplosion(explosion) = entity {
-                if explosion.framecount == 0 {
-                    indexes.push(index);
-                }
-                else {
-                    explosion.framecount -= 1;
-                    if let Right(animation) = &mut explosion.sprite {
-                        animation.step();
-                    }
-                }
+    let mut finished = vec![];
+    for (handle, explosion) in state.get_mut_entities().iter_mut::<BulletExplosion>() {
+        if explosion.framecount == 0 {
+            finished.push(handle);
+        }
+        else {
+            explosion.framecount -= 1;
+            if let Right(animation) = &mut explosion.sprite {
+                animation.step();
             }
         }
     }
+    for handle in finished {
+        state.delete_explosion(handle);
+    }
 
-    for index in &indexes {
-        world.delete_explosion(*index);
+    let mut finished = vec![];
+    for (handle, explosion) in state.get_mut_entities().iter_mut::<UfoExplosion>() {
+        // the explosion is displayed first, followed by the points awarded
+        if explosion.framecount > 0 {
+            explosion.framecount -= 1;
+        }
+        else if explosion.points_framecount > 0 {
+            explosion.points_framecount -= 1;
+        }
+        else {
+            finished.push(handle);
+        }
+    }
+    for handle in finished {
+        state.delete_explosion(handle);
     }
 }
 
 // alien control system, control movement of swarm
-pub fn alien_control_system(world: &mut World) {
+pub fn alien_control_system(state: &mut GameState) {
 
     // is the player in the process of dying, then no updates take place
-    if world.get_player_died() {
+    if state.player_died {
+        return;
+    }
+
+    // the swarm is frozen while an alien explodes
+    if state.exploding_alien.is_some() {
+        if !state.has_alien_explosion_timer_expired() {
+            return;
+        }
+        remove_exploded_alien(state);
+    }
+
+    if state.swarm_movement == SwarmMovement::Faithful {
+        faithful_alien_control_system(state);
         return;
     }
 
-    let bounding_width = 24;
     // step animations 
-    let elasped_time = world.get_lag();
+    let elasped_time = state.get_lag();
 
-    let step = elasped_time >= world.get_alien_swarm_speed();
+    let step = elasped_time >= state.alien_swarm_speed;
     let step_ani = elasped_time.as_millis() % 16 == 0;
-    let top_left_pos = world.get_alien_swarm_top_left_position();
-    // we now account for empty left hand columns
-    // we keep these seperate as our reference point is the whole swam, live or dead
-    let actual_top_left_pos = 
-        Point::new(
-            top_left_pos.x + (world.get_alien_spacing_horz())*world.left_most_alien_column(), 
-            top_left_pos.y);
-
-    let mut direction = world.get_alien_swarm_direction();
-    let speed = world.get_alien_speed();
-
-    let extra: u32 = ((world.get_number_alien_columns() as u32 - 1) - world.right_most_alien_column() as u32) * 6;
-    let top_right_pos = 
-        top_left_pos.x + world.right_most_alien_column() as u32 * 
-            (world.get_alien_spacing_horz() + bounding_width ) + extra; //- 2*world.get_alien_spacing_horz();
-
-    let bounds = world.get_bounds();
+
+    // edges of the live aliens, found from the swarm's grid, so empty columns on either side are ignored,
+    // the right edge is that of the lowest live alien in the right most live column
+    let swarm = state.get_swarm();
+    let left_column = swarm.left_most_column().unwrap_or(0);
+    let right_column = swarm.right_most_column().unwrap_or(0);
+    let alien_width = state.lowest_alive_alien_in_column(right_column)
+        .and_then(|alien| state.get_entity(alien))
+        .map_or(0, |alien| alien.get_bounding_box().size.width);
+    let top_left_x = swarm.column_x(left_column);
+    let top_right_pos = swarm.column_x(right_column) + alien_width;
+
+    let mut direction = state.alien_swarm_direction;
+    let speed = state.alien_speed;
+
+    let bounds = state.get_bounds();
     // resolve alien swarm next move, i.e. change direction or continue on current path
     let mut step_down: u32 = 0;
 
@@ -331,35 +357,33 @@ pub fn alien_control_system(world: &mut World) {
     //     println!("{} {}", top_right_pos, bounds.max_x());
     // }
 
-    if step && top_right_pos >= bounds.max_x() - world.get_alien_speed() as u32 && direction == 1 {
-        *world.get_mut_alien_swarm_direction() = -1;
+    if step && top_right_pos >= bounds.max_x() - state.alien_speed as u32 && direction == 1 {
+        state.alien_swarm_direction = -1;
         direction = -1;
-        step_down = world.get_alien_step_down();
+        step_down = state.get_alien_step_down();
     }
-    else if step && actual_top_left_pos.x  <= bounds.min_x() + world.get_alien_speed() as u32 && direction == -1 {
-        *world.get_mut_alien_swarm_direction() = 1;
+    else if step && top_left_x <= bounds.min_x() + state.alien_speed as u32 && direction == -1 {
+        state.alien_swarm_direction = 1;
         direction = 1;
-        step_down = world.get_alien_step_down();
+        step_down = state.get_alien_step_down();
     }
 
     // if step {
     //     println!(">>>");
     // }
-    for alien_index in 0..world.get_number_aliens() {
-        if let Some(entity) = world.get_mut_entity(world.get_alien(alien_index)) {
-            if let Entity::Alien(alien) = entity {
-                // step internal animation, if necessary 
-                if step_ani {
-                    alien.animation.step();
-                }
+    for alien_index in 0..state.get_number_aliens() {
+        if let Some(alien) = state.get_mut_entity(state.get_alien(alien_index)) {
+            // step internal animation, if necessary 
+            if step_ani {
+                alien.animation.step();
+            }
 
-                // step each alien within swarm, if necessary
-                if step && alien.is_alive {
-                    let x = (alien.position.x as i32 + speed * direction) as u32;
-                    let y = alien.position.y + step_down;
-                    alien.position = Point::new(x, y);
-                    //println!("{:?}", alien.position);
-                }
+            // step each alien within swarm, if necessary
+            if step && alien.is_alive() {
+                let x = (alien.position.x as i32 + speed * direction) as u32;
+                let y = alien.position.y + step_down;
+                alien.position = Point::new(x, y);
+                //println!("{:?}", alien.position);
             }
         }
     }
@@ -369,61 +393,264 @@ pub fn alien_control_system(world: &mut World) {
 
     // update swam overall position for next time
     if step {
-        let pos = world.get_mut_alien_swarm_top_left_postion();
+        let pos = state.get_mut_alien_swarm_top_left_postion();
         pos.x = (pos.x as i32 + speed * direction) as u32;
+        pos.y += step_down;
+
+        // a note of the march for each step of the swarm
+        state.step_march();
+        state.reset_lag();
+    }
+}
 
-        world.reset_lag();
+// faithful alien control system, moves a single live alien each tick, as the original did once per interrupt.
+// the swarm speeds up naturally as aliens are killed and the formation ripples as it moves
+fn faithful_alien_control_system(state: &mut GameState) {
+    if state.get_lag() < state.get_alien_tick_duration() {
+        return;
+    }
+    state.reset_lag();
+
+    // find the next live alien to move, starting a new pass of the swarm if we wrap around
+    let number_aliens = state.get_number_aliens();
+    let mut next = None;
+    for _ in 0..number_aliens {
+        let cursor = state.swarm_cursor;
+        if cursor == 0 {
+            // an alien reached the boundary on the last pass, so reverse and move down a row
+            if state.swarm_reverse {
+                state.alien_swarm_direction *= -1;
+                state.swarm_step_down = state.get_alien_step_down();
+                state.swarm_reverse = false;
+            }
+            else {
+                state.swarm_step_down = 0;
+            }
+
+            // keep swarm's overall position in step with the aliens
+            let direction = state.alien_swarm_direction;
+            let speed = state.get_faithful_alien_speed(direction);
+            let step_down = state.swarm_step_down;
+            let pos = state.get_mut_alien_swarm_top_left_postion();
+            pos.x = (pos.x as i32 + speed * direction) as u32;
+            pos.y += step_down;
+
+            // a note of the march for each pass of the swarm, i.e. each time it has stepped
+            state.step_march();
+        }
+
+        let alien_index = state.get_swarm_order_alien(cursor);
+        state.swarm_cursor = (cursor + 1) % number_aliens;
+
+        if let Some(alien) = state.get_entity(state.get_alien(alien_index)) {
+            if alien.is_alive() {
+                next = Some(alien_index);
+                break;
+            }
+        }
+    }
+
+    let bounds = state.get_bounds();
+    let direction = state.alien_swarm_direction;
+    let speed = state.get_faithful_alien_speed(direction);
+    let step_down = state.swarm_step_down;
+    let mut reverse = false;
+
+    if let Some(alien_index) = next {
+        if let Some(alien) = state.get_mut_entity(state.get_alien(alien_index)) {
+            let x = (alien.position.x as i32 + speed * direction) as u32;
+            let y = alien.position.y + step_down;
+            alien.position = Point::new(x, y);
+            alien.animation.next_frame();
+
+            // has the alien reached the boundary? if so, swarm reverses on the next pass
+            let width = alien.get_bounding_box().size.width;
+            reverse =
+                (direction == 1 && x + width >= bounds.max_x() - speed as u32) ||
+                (direction == -1 && x <= bounds.min_x() + speed as u32);
+        }
+    }
+
+    if reverse {
+        state.swarm_reverse = true;
     }
 }
 
-pub fn ship_control_system(world: &mut World) {
+pub fn ship_control_system(state: &mut GameState) {
     // if the player in the process of dying, then no updates take place
-    if world.get_player_died() {
+    if state.player_died {
         return;
     }
 
-    let bounds = world.get_bounds();
-    let ufo_timer_expired = world.has_ufo_timer_expired();
+    let bounds = state.get_bounds();
+    let mut ufo_timer_expired = state.has_ufo_timer_expired();
+
+    // the original UFO does not appear when there are fewer than 8 aliens left
+    if ufo_timer_expired && 
+       state.arcade_ufo && 
+       state.get_number_aliens() as i32 - state.alien_dead < UFO_ARCADE_MIN_ALIENS {
+        ufo_timer_expired = false;
+        state.reset_ufo_timer();
+    }
+
     let mut reset_timer = false;
     let mut play_effect = false;
-    if let Some(entity) = world.get_mut_entity(world.get_ship()) {
-        if let Entity::Ship(ship) = entity {
-            if ship.is_alive {
-                ship.position.x += World::ship_movement();
-
-                // has ship make it to the right edge of the window?
-                if ship.position.x + ship.bounding_box.size.width >= bounds.max_x() {
-                    ship.is_alive = false;
-                    reset_timer = true;
-                }
-            }
-            else if ufo_timer_expired {
-                ship.is_alive = true;
-                play_effect = true;
-                ship.position = Point::new(UFO_START_X_START_POSITION, UFO_START_Y_START_POSITION);
+    let mut ufo_moved = false;
+    let mut ufo_x = 0;
+    if let Some(ship) = state.get_mut_entity(state.get_ship()) {
+        if ship.is_alive {
+            ship.position.x += World::ship_movement();
+            ufo_moved = true;
+
+            // has ship make it to the right edge of the window?
+            if ship.position.x + ship.bounding_box.size.width >= bounds.max_x() {
+                ship.is_alive = false;
+                reset_timer = true;
             }
         }
+        else if ufo_timer_expired {
+            ship.is_alive = true;
+            play_effect = true;
+            ship.position = Point::new(UFO_START_X_START_POSITION, UFO_START_Y_START_POSITION);
+        }
+        ufo_x = ship.position.x + ship.bounding_box.size.width / 2;
     }
 
-    // if the UFO got the edge of the screen we need to reset its timer and stop sound effect
+    // if the UFO got the edge of the screen we need to reset its timer
     if reset_timer {
-        world.reset_ufo_timer();
-        world.pause_ufo();
+        state.reset_ufo_timer();
+        state.push_event(GameEvent::UfoEscaped);
     }
-    // if UFO entering the screen enable ufo sound effect
+    // UFO entering the screen
     else if play_effect {
-        world.play_ufo();
+        state.push_event(GameEvent::UfoSpawned { x: ufo_x });
+    }
+    // UFO crossing the screen
+    else if ufo_moved {
+        state.push_event(GameEvent::UfoMoved { x: ufo_x });
     }
 }
 
 
 /// is it game over, i.e. player has no lives left?
-pub fn is_game_over(world: &World) -> bool {
-    if let Some(entity) = world.get_entity(world.get_player()) {
-        if let Entity::Player(player) = entity {
-            return player.lives_remaining == 0;
-        }
+pub fn is_game_over(state: &GameState) -> bool {
+    if let Some(player) = state.get_entity(state.get_player()) {
+        return player.lives_remaining == 0;
     }
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::tests::new_game_state;
+
+    /// returns the position of every alien, in cell order
+    fn alien_positions(state: &GameState) -> Vec<Point> {
+        (0..state.get_number_aliens())
+            .map(|cell| state.get_entity(state.get_alien(cell)).unwrap().position)
+            .collect()
+    }
+
+    /// move the game's clock on by a tick of the swarm, and run the swarm, returning the cells of the aliens
+    /// that moved
+    fn swarm_tick(state: &mut GameState) -> Vec<usize> {
+        let before = alien_positions(state);
+        state.advance_time(state.get_alien_tick_duration());
+        state.update();
+        alien_control_system(state);
+        let after = alien_positions(state);
+        (0..before.len()).filter(|cell| before[*cell] != after[*cell]).collect()
+    }
+
+    #[test]
+    fn faithful_swarm_moves_one_alien_per_tick() {
+        let (_, mut state) = new_game_state();
+        state.swarm_movement = SwarmMovement::Faithful;
+
+        // a dead alien, in the bottom row, is skipped
+        let columns = state.get_swarm().get_number_columns();
+        let rows = state.get_swarm().get_number_rows();
+        let dead = state.get_swarm().cell(1, rows - 1);
+        state.get_mut_swarm().kill(dead);
+        state.get_mut_entity(state.get_alien(dead)).unwrap().state = AlienState::Dead;
+
+        // from the bottom left, along each row, then up a row
+        let mut expected: Vec<usize> = (0..rows).rev()
+            .flat_map(|row| (0..columns).map(move |column| row * columns + column))
+            .filter(|cell| *cell != dead)
+            .collect();
+        // and around again, for the start of the next pass
+        expected.push(state.get_swarm().cell(0, rows - 1));
+
+        let mut moved = vec![];
+        let mut march_steps = 0;
+        for _ in 0..expected.len() {
+            let cells = swarm_tick(&mut state);
+            assert_eq!(cells.len(), 1, "exactly one alien moves each tick");
+            moved.push(cells[0]);
+            march_steps += state.get_events().filter(|event| matches!(event, GameEvent::MarchStep { .. })).count();
+        }
+        assert_eq!(moved, expected);
+        // a note for each pass of the swarm
+        assert_eq!(march_steps, 2);
+    }
+
+    #[test]
+    fn swarm_is_frozen_while_an_alien_explodes() {
+        let mut frozen_ticks = vec![];
+        for swarm_movement in [SwarmMovement::Timed, SwarmMovement::Faithful] {
+            let (_, mut state) = new_game_state();
+            state.swarm_movement = swarm_movement;
+
+            let cell = state.get_swarm().cell(0, 0);
+            state.start_alien_explosion(cell);
+            let mut ticks = 0;
+            while state.exploding_alien.is_some() {
+                let moved = swarm_tick(&mut state);
+                if state.exploding_alien.is_some() {
+                    assert!(moved.is_empty(), "{:?} swarm moved during an explosion", swarm_movement);
+                    ticks += 1;
+                }
+            }
+            // long enough that the timed swarm would have stepped, had it not been frozen
+            assert!(state.get_alien_tick_duration() * ticks > state.alien_swarm_speed);
+            assert_eq!(state.get_entity(state.get_alien(cell)).unwrap().state, AlienState::Dead);
+            frozen_ticks.push(ticks);
+
+            // and moves again once the explosion has gone
+            let mut moved = vec![];
+            while moved.is_empty() {
+                moved = swarm_tick(&mut state);
+            }
+            assert!(!moved.contains(&cell));
+        }
+        assert_eq!(frozen_ticks[0], frozen_ticks[1]);
+    }
+
+    #[test]
+    fn ufo_points_shown_once_the_explosion_finishes() {
+        let (assets, mut state) = new_game_state();
+        let explosion_time = state.get_ufo_explosion_time() as i32;
+        let points_time = state.get_ufo_points_time() as i32;
+        let handle = state.add_explosion(
+            UfoExplosion::new(Point::new(100, 40), assets.get_ufo_explosion_sprite(), 150, explosion_time, points_time));
+
+        // what is displayed each frame, until the explosion is removed
+        let mut explosion_frames = 0;
+        let mut points_frames = 0;
+        while let Some(explosion) = state.get_entity(handle) {
+            if explosion.framecount > 0 {
+                assert_eq!(points_frames, 0, "the explosion is shown before the points");
+                explosion_frames += 1;
+            }
+            else if explosion.points_framecount > 0 {
+                points_frames += 1;
+            }
+            bullet_control_system(&mut state);
+        }
+        assert_eq!(explosion_frames, explosion_time);
+        assert_eq!(points_frames, points_time);
+    }
+}
@@ -10,17 +10,17 @@
 
 use either::*;
 
-use crate::sprite_sheet::{Sprite, SpriteMask};
+use crate::sprite_sheet::SpriteMask;
+use crate::assets::{Assets, SpriteHandle};
 use crate::animation::*;
 use crate::math::*;
-
-pub type EntityIndex = usize;
+use crate::entity_store::EntityKind;
 
 pub const PLAYER_START_LIVES: i32 = 3;
 pub const PLAYER_INITIAL_SCORE: i32 = 0;
 
 /// current state of a bullet
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BulletMode {
     /// bullet loaded and ready to be fired
     Fire,
@@ -31,7 +31,7 @@ pub enum BulletMode {
 }
 
 /// different types of alien bullets
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlienBulletType {
     Rolling = 0,
     Plunger = 1,
@@ -52,12 +52,12 @@ impl AlienBulletType {
 }
 
 /// Representation of bullet entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bullet {
     /// screen position of bullet
     pub position: Point,
     /// some bullets have only a single sprite, some are animated by many
-    pub sprite: Either<Sprite,Animation>,
+    pub sprite: Either<SpriteHandle,Animation>,
     /// current status of bullet, e.g. is in in flight
     pub bullet_mode: BulletMode,
     /// axis alined bounding box
@@ -72,7 +72,7 @@ impl Bullet {
     /// * `position` - initial position of bullet
     /// * `sprite`   - sprite or animation used to render bullet
     /// * `bounding_box` - bounding box of bullet, used in collision detection
-    pub fn new(position: Point, sprite: Either<Sprite,Animation>, bounding_box: Rect,) -> Self {
+    pub fn new(position: Point, sprite: Either<SpriteHandle,Animation>, bounding_box: Rect,) -> Self {
         Bullet {
             position,
             sprite,
@@ -81,6 +81,18 @@ impl Bullet {
         }
     }
 
+    /// returns the mask of bullet's sprite, or current frame of its animation
+    /// 
+    /// # Arguments
+    /// 
+    /// * `assets` - Assets the bullet's sprites are in
+    pub fn get_mask<'a>(&self, assets: &'a Assets) -> &'a SpriteMask {
+        match &self.sprite {
+            Left(sprite) => assets.get_sprite(*sprite).get_mask(),
+            Right(animation) => animation.get_mask(assets),
+        }
+    }
+
     /// returns a copy of bullet's bounding box
     pub fn get_bounding_box(&self) -> Rect {
         Rect::new(
@@ -90,12 +102,12 @@ impl Bullet {
 }
 
 /// Representation of bullet explosion entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BulletExplosion {
     /// position of bullet explosion on screen
     pub position: Point,
     /// sprite or animation used to render explosion
-    pub sprite: Either<Sprite,Animation>,
+    pub sprite: Either<SpriteHandle,Animation>,
     /// how long it should be live/displayed for
     pub framecount: i32,
 }
@@ -108,7 +120,7 @@ impl BulletExplosion {
     /// * `position` - position of explosion on screen
     /// * `sprite` - sprite or animation used to render explosion
     /// * `framecount` - how long should the explosion live/displayed
-    pub fn new(position: Point, sprite: Either<Sprite,Animation>, framecount: i32) -> Self {
+    pub fn new(position: Point, sprite: Either<SpriteHandle,Animation>, framecount: i32) -> Self {
         BulletExplosion {
             position,
             sprite,
@@ -118,13 +130,49 @@ impl BulletExplosion {
 }
 
 
+/// explosion displayed in the ship's place when it is hit, followed by the points awarded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UfoExplosion {
+    /// position of explosion on screen
+    pub position: Point,
+    /// sprite used to render explosion
+    pub sprite: SpriteHandle,
+    /// points awarded for hitting the ship
+    pub points: i32,
+    /// how long should the explosion live/displayed
+    pub framecount: i32,
+    /// how long should the points be displayed, once the explosion has finished
+    pub points_framecount: i32,
+}
+
+impl UfoExplosion {
+    /// create a new ufo explosion
+    /// 
+    /// # Arguments
+    /// 
+    /// * `position` - position of explosion on screen
+    /// * `sprite` - sprite used to render explosion
+    /// * `points` - points awarded, displayed after the explosion
+    /// * `framecount` - how long should the explosion live/displayed
+    /// * `points_framecount` - how long should the points be displayed
+    pub fn new(position: Point, sprite: SpriteHandle, points: i32, framecount: i32, points_framecount: i32) -> Self {
+        UfoExplosion {
+            position,
+            sprite,
+            points,
+            framecount,
+            points_framecount,
+        }
+    }
+}
+
 /// Representation of player entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Player {
     /// screen position of barrier
     pub position: Point,
     /// player sprite
-    pub sprite: Sprite,
+    pub sprite: SpriteHandle,
     /// bullet entity for player, as they can only ever be one we keep it here
     pub bullet: Bullet,
     /// axis alined bounding box
@@ -143,13 +191,14 @@ impl Player {
     /// * `position` - initial position of player on screen
     /// * `sprite` - sprite used to render player
     /// * `bounding_box` - bounding box for player sprite
-    pub fn new(position: Point, sprite: Sprite, bullet: Bullet, bounding_box: Rect) -> Self {
+    /// * `lives_remaining` - number of lives player starts with
+    pub fn new(position: Point, sprite: SpriteHandle, bullet: Bullet, bounding_box: Rect, lives_remaining: i32) -> Self {
         Player {
             position,
             sprite,
             bullet,
             bounding_box,
-            lives_remaining: PLAYER_START_LIVES,
+            lives_remaining,
             score: PLAYER_INITIAL_SCORE,
         }
     }
@@ -163,12 +212,12 @@ impl Player {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Barrier {
     /// screen position of barrier 
     pub position: Point,
     /// barrier sprite
-    pub sprite: Sprite,
+    pub sprite: SpriteHandle,
     /// mask used for colisions with sprite... when the barrier is hit the mask is update to represent the explosion
     pub mask: SpriteMask,
     /// axis alined bounding box
@@ -177,7 +226,7 @@ pub struct Barrier {
 
 impl Barrier {
     /// create an barrier
-    pub fn new(position: Point, sprite: Sprite, mask: SpriteMask, bounding_box: Rect,) -> Self {
+    pub fn new(position: Point, sprite: SpriteHandle, mask: SpriteMask, bounding_box: Rect,) -> Self {
         Barrier {
             position,
             sprite,
@@ -195,14 +244,25 @@ impl Barrier {
 }
 
 
-#[derive(Debug, Clone)]
+/// current state of an alien
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlienState {
+    /// alien is part of the swarm
+    Alive,
+    /// alien has been hit and its explosion is displayed in its place, the swarm is frozen until it has finished
+    Exploding,
+    /// alien has been removed from the swarm
+    Dead,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Alien {
     /// screen position of alien
     pub position: Point,
     pub points: i32,
     pub bounding_box: Rect,
     pub animation: Animation,
-    pub is_alive: bool,
+    pub state: AlienState,
 }
 
 impl Alien {
@@ -216,10 +276,16 @@ impl Alien {
             points,
             bounding_box,
             animation,
-            is_alive: true,
+            state: AlienState::Alive,
         }
     }
 
+    /// is alien alive and part of the swarm, i.e. not exploding or dead
+    #[inline]
+    pub fn is_alive(&self) -> bool {
+        self.state == AlienState::Alive
+    }
+
     pub fn get_bounding_box(&self) -> Rect {
         // TODO: resolve the *4 hack!!
         Rect::new(
@@ -228,12 +294,12 @@ impl Alien {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Ship {
     /// screen position of ship
     pub position: Point,
     /// sprite drawn for the ship
-    pub sprite: Sprite,
+    pub sprite: SpriteHandle,
     /// ships bounding box
     pub bounding_box: Rect,
     /// when ship is shot this is the additional points the player receives 
@@ -246,7 +312,7 @@ impl Ship {
     pub fn new(       
         position: Point, 
         bounding_box: Rect,
-        sprite: Sprite) -> Self {
+        sprite: SpriteHandle) -> Self {
         Ship {
             position,
             sprite,
@@ -269,12 +335,49 @@ impl Ship {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Entity {
     Player(Player),
     Alien(Alien),
     Ship(Ship),
     Bullet(Bullet),
     BulletExplosion(BulletExplosion),
+    UfoExplosion(UfoExplosion),
     Barrier(Barrier),
-}
\ No newline at end of file
+}
+
+/// implement EntityKind for the type wrapped by a variant of Entity
+macro_rules! entity_kind {
+    ($kind:ident) => {
+        impl EntityKind for $kind {
+            #[inline]
+            fn from_entity(entity: &Entity) -> Option<&Self> {
+                match entity {
+                    Entity::$kind(e) => Some(e),
+                    _ => None,
+                }
+            }
+
+            #[inline]
+            fn from_entity_mut(entity: &mut Entity) -> Option<&mut Self> {
+                match entity {
+                    Entity::$kind(e) => Some(e),
+                    _ => None,
+                }
+            }
+
+            #[inline]
+            fn into_entity(self) -> Entity {
+                Entity::$kind(self)
+            }
+        }
+    };
+}
+
+entity_kind!(Player);
+entity_kind!(Alien);
+entity_kind!(Ship);
+entity_kind!(Bullet);
+entity_kind!(BulletExplosion);
+entity_kind!(UfoExplosion);
+entity_kind!(Barrier);
\ No newline at end of file
@@ -0,0 +1,222 @@
+//! Description:
+//!
+//! Store for the game's entities. Entities are spawned into slots, and referred to by handles, which are typed
+//! by the kind of entity they refer to, e.g. `Handle<Alien>`. Each slot has a generation, which is bumped when
+//! its entity is despawned, so a handle to a despawned entity never refers to whatever is spawned into its slot
+//! next, it simply no longer finds anything. Handles to other entities are unaffected by spawning and despawning.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::entity::Entity;
+
+/// a kind of entity, i.e. one of the types wrapped by `Entity`
+pub trait EntityKind: Sized + 'static {
+    /// returns the entity as this kind, if it is one
+    fn from_entity(entity: &Entity) -> Option<&Self>;
+    /// returns the entity as this kind, if it is one
+    fn from_entity_mut(entity: &mut Entity) -> Option<&mut Self>;
+    /// wrap as an entity
+    fn into_entity(self) -> Entity;
+}
+
+/// handle to an entity of a given kind, which remains valid until that entity is despawned
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Handle<T> {
+    /// slot entity is in
+    index: usize,
+    /// generation of slot when entity was spawned
+    generation: u32,
+    #[serde(skip)]
+    _kind: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize, generation: u32) -> Self {
+        Handle {
+            index,
+            generation,
+            _kind: PhantomData,
+        }
+    }
+}
+
+// derived traits would require the same of T, which is only a marker
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> { }
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> { }
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Slot {
+    /// bumped each time slot's entity is despawned
+    generation: u32,
+    entity: Option<Entity>,
+}
+
+/// generational store of entities
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntityStore {
+    slots: Vec<Slot>,
+    /// slots without an entity, reused before new slots are added
+    free: Vec<usize>,
+}
+
+impl EntityStore {
+    /// create an empty store
+    pub fn new() -> Self {
+        EntityStore::default()
+    }
+
+    /// add an entity, returning its handle
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Entity to add
+    pub fn spawn<T: EntityKind>(&mut self, entity: T) -> Handle<T> {
+        let entity = Some(entity.into_entity());
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index].entity = entity;
+                index
+            },
+            None => {
+                self.slots.push(Slot { generation: 0, entity });
+                self.slots.len() - 1
+            },
+        };
+        Handle::new(index, self.slots[index].generation)
+    }
+
+    /// remove an entity, returns false if it had already been removed
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Entity to remove
+    pub fn despawn<T: EntityKind>(&mut self, handle: Handle<T>) -> bool {
+        match self.slots.get_mut(handle.index) {
+            Some(slot) if slot.generation == handle.generation && slot.entity.is_some() => {
+                slot.entity = None;
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free.push(handle.index);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// returns an entity, or None if it has been despawned
+    #[inline]
+    pub fn get<T: EntityKind>(&self, handle: Handle<T>) -> Option<&T> {
+        self.slots.get(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.entity.as_ref())
+            .and_then(T::from_entity)
+    }
+
+    /// returns an entity, or None if it has been despawned
+    #[inline]
+    pub fn get_mut<T: EntityKind>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.slots.get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.entity.as_mut())
+            .and_then(T::from_entity_mut)
+    }
+
+    /// iterator over entities of a kind, with their handles, in the order of their slots
+    pub fn iter<T: EntityKind>(&'_ self) -> impl Iterator<Item = (Handle<T>, &T)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.entity.as_ref()
+                .and_then(T::from_entity)
+                .map(|entity| (Handle::new(index, slot.generation), entity))
+        })
+    }
+
+    /// iterator over entities of a kind, with their handles, in the order of their slots
+    pub fn iter_mut<T: EntityKind>(&'_ mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> + '_ {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            let generation = slot.generation;
+            slot.entity.as_mut()
+                .and_then(T::from_entity_mut)
+                .map(|entity| (Handle::new(index, generation), entity))
+        })
+    }
+
+    /// returns handles of every entity of a kind, for when entities are updated, or despawned, while iterating
+    pub fn handles<T: EntityKind>(&self) -> Vec<Handle<T>> {
+        self.iter::<T>().map(|(handle, _)| handle).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::*;
+    use crate::animation::Animation;
+    use crate::assets::SpriteHandle;
+    use crate::entity::{Alien, Ship};
+
+    /// returns an alien, with points to tell it apart
+    fn alien(points: i32) -> Alien {
+        Alien::new(Point::new(0, 0), points, Rect::zero(), Animation::default())
+    }
+
+    #[test]
+    fn despawned_handles_are_invalid() {
+        let mut store = EntityStore::new();
+        let first = store.spawn(alien(10));
+        let second = store.spawn(alien(20));
+
+        assert!(store.despawn(first));
+        assert!(store.get(first).is_none());
+        assert!(store.get_mut(first).is_none());
+        assert!(!store.despawn(first));
+
+        // other entities are unaffected
+        assert_eq!(store.get(second).map(|a| a.points), Some(20));
+        assert_eq!(store.handles::<Alien>(), vec![second]);
+    }
+
+    #[test]
+    fn reused_slots_do_not_revive_old_handles() {
+        let mut store = EntityStore::new();
+        let first = store.spawn(alien(10));
+        store.despawn(first);
+
+        // the free slot is reused, by a different kind of entity
+        let ship = store.spawn(Ship::new(Point::new(0, 0), Rect::zero(), SpriteHandle::default()));
+        assert_eq!(store.slots.len(), 1);
+        assert!(store.get(first).is_none());
+        assert!(store.get(ship).is_some());
+        assert_eq!(store.iter::<Alien>().count(), 0);
+
+        // and again by the same kind
+        store.despawn(ship);
+        let third = store.spawn(alien(30));
+        assert_eq!(store.slots.len(), 1);
+        assert_ne!(third, first);
+        assert!(store.get(first).is_none());
+        assert!(!store.despawn(first));
+        assert_eq!(store.get(third).map(|a| a.points), Some(30));
+    }
+}
@@ -0,0 +1,51 @@
+//! Description:
+//!
+//! Game events, pushed to the game's state by the systems as things happen during play. Events are
+//! available to any system that wants them, e.g. audio, until the end of the current frame,
+//! when they are cleared.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use crate::game_state::*;
+
+/// something that happened during play
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    /// swarm stepped, playing the next note of the march
+    MarchStep { note: usize },
+    /// player fired a bullet, from the cannon at x
+    ShotFired { x: u32 },
+    /// alien hit by player's bullet, row 0 is the top of the swarm, x is the alien's centre
+    AlienKilled { row: usize, x: u32 },
+    /// player hit by alien bullet
+    PlayerKilled,
+    /// UFO entered the screen at x
+    UfoSpawned { x: u32 },
+    /// UFO moved across the screen to x
+    UfoMoved { x: u32 },
+    /// UFO left the screen without being hit
+    UfoEscaped,
+    /// UFO hit by player's bullet
+    UfoKilled { points: i32 },
+    /// last alien of the swarm removed
+    LevelCleared,
+    /// player awarded an extra life
+    ExtraLife,
+}
+
+/// log events, useful for debugging gameplay
+///
+/// # Arguments
+///
+/// * `state` - State of the game
+pub fn event_log_system(state: &GameState) {
+    for event in state.get_events() {
+        match event {
+            GameEvent::MarchStep { note } => log::trace!("march note {}", note),
+            GameEvent::AlienKilled { row, .. } => log::debug!("alien killed in row {}", row),
+            GameEvent::UfoMoved { x } => log::trace!("ufo moved to {}", x),
+            GameEvent::UfoKilled { points } => log::debug!("ufo killed for {} points", points),
+            _ => log::debug!("{:?}", event),
+        }
+    }
+}
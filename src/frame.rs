@@ -31,6 +31,25 @@ mod frameinner {
                 self.frame[offset..offset + 4].copy_from_slice(rgba);
             } 
         }
+
+        /// returns a pixel, 4 bytes, to be drawn over, or None if it is outside of the framebuffer
+        pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
+            if x < self.width*4 {
+                let offset = (x+y*self.width*4) as usize;
+                self.frame.get_mut(offset..offset + 4)
+            }
+            else {
+                None
+            }
+        }
+
+        /// returns a row of pixels, 4 bytes per pixel, to be drawn over, or None if it is outside of the 
+        /// framebuffer
+        pub fn row_mut(&mut self, y: u32) -> Option<&mut [u8]> {
+            let row = (self.width*4) as usize;
+            let offset = y as usize * row;
+            self.frame.get_mut(offset..offset + row)
+        }
     }
 
 }
@@ -58,6 +77,14 @@ mod frameinner {
             if x < self.width*4 && y < self.height*4 {
             } 
         }
+
+        pub fn pixel_mut(&mut self, _x: u32, _y: u32) -> Option<&mut [u8]> {
+            None
+        }
+
+        pub fn row_mut(&mut self, _y: u32) -> Option<&mut [u8]> {
+            None
+        }
     }
 }
 
@@ -0,0 +1,823 @@
+//! Description: 
+//! 
+//! State of a game, as plain data, so that it can be cloned, or serialised, to take a snapshot, e.g. for save 
+//! states, rollback, or tests. Entities refer to their sprites by handle, so sprites, sound, and so on, live
+//! outside of it, in the assets and the world.
+//! 
+//! Timers run on the game's clock, which the world advances from the wall clock, so a restored snapshot
+//! carries on from where it was taken.
+//! 
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::time::Duration;
+use rand::{RngCore, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::entity::*;
+use crate::config::*;
+use crate::world::*;
+use crate::timer::*;
+use crate::text::GameOverProgress;
+use crate::event::*;
+use crate::math::*;
+use crate::swarm::*;
+use crate::entity_store::*;
+
+//------------------------------------------------------------------------------
+
+type Time = Duration;
+
+//------------------------------------------------------------------------------
+// Constants used by the rules of the game, generally only accessable via the game's state
+//------------------------------------------------------------------------------
+
+const PLAYER_BULLET_SPEED: i32 = 6;
+
+const BULLET_EXPLOSION_TIME: u64 = 24;
+const UFO_EXPLOSION_TIME: u64 = 24;
+// points awarded for the ufo are displayed for about a second, once it has exploded
+const UFO_POINTS_TIME: u64 = 60;
+
+const PLAYER_DIED_DURATION: Time = Duration::from_millis(1000);
+const NEXT_LEVEL_DURATION: Time = Duration::from_millis(500);
+const GAME_OVER_DURATION: Time = Duration::from_millis(2000);
+
+const MIN_UFO_TIMER_DURATION: u64 = 6;
+const MAX_UFO_TIMER_DURATION: u64 = 20;
+
+// the original UFO, appears every 0x600 frames, only if there are at least 8 aliens, and scores from a table
+// indexed by the number of shots the player has fired
+const UFO_ARCADE_TIMER_DURATION: Time = Duration::from_millis(25600);
+pub const UFO_ARCADE_MIN_ALIENS: i32 = 8;
+const UFO_DEFAULT_POINTS: i32 = 100;
+const UFO_POINTS_TABLE: [i32; 15] = [100, 50, 50, 100, 150, 100, 100, 50, 300, 100, 100, 100, 50, 150, 100];
+
+const MAX_CREDITS: u32 = 99;
+
+pub const ALIEN_INITIAL_SPEED: i32 = 2;
+const ALIEN_SWARM_INITIAL_SPEED: Time = Duration::from_millis(120);
+const ALIEN_BULLET_START_DURATION: Time = Duration::from_millis(1000);
+const ALIEN_BULLET_LESS_EIGHT_DURATION: Time = Duration::from_millis(70);
+// the original's alien reload rate, (score below, frames between shots at 60Hz)
+const ALIEN_BULLET_ARCADE_RELOAD: [(u32, Time); 4] = [
+    (200, Duration::from_millis(800)),
+    (1000, Duration::from_millis(267)),
+    (2000, Duration::from_millis(183)),
+    (3000, Duration::from_millis(133)),
+];
+const ALIEN_BULLET_ARCADE_MIN_RELOAD: Time = Duration::from_millis(117);
+const ANIMATE_ALIEN_BULLET_DURATION: Time = Duration::from_millis(20);
+const ALIEN_STEP_DOWN: u32 = 8;
+// the original froze the swarm for 16 frames while an alien exploded
+const ALIEN_EXPLOSION_DURATION: Time = Duration::from_millis(266);
+
+// faithful swarm movement, one alien is moved each tick
+const ALIEN_FAITHFUL_TICK_DURATION: Time = Duration::from_millis(16);
+const ALIEN_FAITHFUL_SPEED: i32 = 16;
+const ALIEN_FAITHFUL_LAST_ALIEN_RIGHT_SPEED: i32 = 24;
+// number of notes in the march
+const MARCH_NOTES: usize = 4;
+
+/// Current mode of game
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// actively being played
+    Playing,
+    /// inbetween levels
+    NextLevel,
+    /// gameplay is paused 
+    Paused,
+    /// displaying splash screen
+    Splash,
+    /// operator is editing settings
+    Settings,
+    /// player has lost all lives and inbetween new game state
+    GameOver,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    /// current mode of the game
+    pub mode: GameMode,
+
+    // rules, set from configuration
+
+    /// number of lives player starts a game with
+    pub starting_lives: i32,
+    /// score at which the player is awarded an extra life
+    pub bonus_life_score: u32,
+    /// coins needed for credits
+    pub coinage: Coinage,
+    /// can a game be started without a credit
+    pub free_play: bool,
+    /// do aliens reload their bullets as in the original
+    pub arcade_alien_reload: bool,
+    /// does the UFO follow the original's timing and scoring
+    pub arcade_ufo: bool,
+    /// do bullets only hit where their sprites' pixels overlap, rather than their bounding boxes
+    pub pixel_perfect_collisions: bool,
+    /// how the alien swarm is moved
+    pub swarm_movement: SwarmMovement,
+
+    // cabinet
+
+    /// current high score
+    pub high_score: u32,
+    /// coins inserted that have not yet been turned into credits
+    pub coins: u32,
+    /// credits available to start games
+    pub credits: u32,
+
+    // clock
+
+    /// how long the game has been running, timers are measured against it
+    time: Duration,
+    /// game time when the game was last updated
+    previous_time: Duration,
+    /// game time elapsed since the swarm last moved
+    lag: Duration,
+
+    /// bounding box for playable area
+    bounds: Rect,
+
+    /// random number generator, part of the state so that a restored game makes the same choices
+    rng: ChaCha8Rng,
+
+    // game in play
+
+    /// current level
+    pub current_level: u32,
+    /// has the extra life been awarded this game
+    pub bonus_life_awarded: bool,
+    /// number of shots player has fired this game, used for the original's UFO scoring
+    pub player_shots: u32,
+    /// has player died
+    pub player_died: bool,
+    /// player died timer, used to delay when player was killed
+    pub player_died_timer: Timer,
+    /// gameover timer, used to delay when player has losted and going back to new game screen
+    pub game_over_timer: Timer,
+    /// next level timer, used to delay next level
+    pub next_level_timer: Timer,
+    /// when is the next ufo to enter the play
+    pub ufo_timer: Timer,
+    /// game over text displayed so far
+    game_over: GameOverProgress,
+
+    // alien swarm
+
+    /// next note of the march
+    pub march_note: usize,
+    /// alien that is currently exploding, if any
+    pub exploding_alien: Option<usize>,
+    /// alien explosion timer, the swarm is frozen until it has expired
+    pub alien_explosion_timer: Timer,
+    pub alien_swarm_direction: i32,
+    /// current speed of alien swarm
+    pub alien_swarm_speed: Duration,
+    pub alien_speed: i32,
+    /// faithful movement, position within the swarm of the next alien to be moved
+    pub swarm_cursor: usize,
+    /// faithful movement, amount each alien moves down during the current pass of the swarm
+    pub swarm_step_down: u32,
+    /// faithful movement, an alien has reached the boundary and the swarm reverses on the next pass
+    pub swarm_reverse: bool,
+    pub alien_dead: i32,
+
+    /// grid of the swarm, with its top left position, tracking live aliens, and the number in, and lowest of,
+    /// each column. this is used to find aliens near a bullet, which aliens can fire, and when alien swarm should
+    /// move down a row, ie. when it has reached boundary
+    swarm: SwarmGrid,
+
+    // alien bullets
+
+    pub next_alien_bullet_time: Timer,
+    pub next_alien_bullet_type: AlienBulletType,
+    pub animate_alien_bullet_time: Timer,
+
+    // entities
+
+    /// every entity, barriers and explosions are found by kind, aliens by the swarm's grid
+    entities: EntityStore,
+    player: Handle<Player>,
+    /// alien bullets, indexed by AlienBulletType
+    alien_bullets: [Handle<Bullet>;3],
+    /// events raised during the current frame, cleared at the end of each frame
+    events: Vec<GameEvent>,
+    ship: Handle<Ship>,
+}
+
+/// entities a game starts with, which are added to its store
+pub struct InitialEntities {
+    pub player: Player,
+    pub barriers: Vec<Barrier>,
+    /// rolling, plunger and squiggly alien bullets, indexed by AlienBulletType
+    pub alien_bullets: [Bullet;3],
+    /// aliens, in row order from the top left of the swarm
+    pub aliens: Vec<Alien>,
+    pub ship: Ship,
+}
+
+impl GameState {
+    /// creates the state of a game, showing the splash screen
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - Bounding box of the playable area
+    /// * `high_score` - Current high score
+    /// * `alien_swarm_direction` - Direction swarm moves in to begin with
+    /// * `alien_swarm_top_left_position` - Top left position of swarm
+    /// * `entities` - Entities the game starts with
+    pub fn new(
+        bounds: Rect,
+        high_score: u32,
+        alien_swarm_direction: i32,
+        alien_swarm_top_left_position: Point,
+        entities: InitialEntities) -> Self {
+
+        let InitialEntities { player, barriers, alien_bullets, aliens, ship } = entities;
+        let [alien_bullet1, alien_bullet2, alien_bullet3] = alien_bullets;
+
+        // add player, UFO, and alien bullet entities
+        let mut entities = EntityStore::new();
+        let player = entities.spawn(player);
+        let ship = entities.spawn(ship);
+        let alien_bullets = [
+            entities.spawn(alien_bullet1),
+            entities.spawn(alien_bullet2),
+            entities.spawn(alien_bullet3)];
+
+        // add barrier entities
+        for barrier in barriers {
+            entities.spawn(barrier);
+        }
+
+        // add alien entities, in row order, as the swarm's grid expects
+        let aliens = aliens.into_iter().map(|alien| entities.spawn(alien)).collect();
+
+        GameState {
+            mode: GameMode::Splash,
+            starting_lives: PLAYER_START_LIVES,
+            bonus_life_score: DEFAULT_BONUS_LIFE_SCORE,
+            coinage: Coinage::default(),
+            free_play: true,
+            arcade_alien_reload: false,
+            arcade_ufo: false,
+            pixel_perfect_collisions: false,
+            swarm_movement: SwarmMovement::default(),
+            high_score,
+            coins: 0,
+            credits: 0,
+            time: Duration::ZERO,
+            previous_time: Duration::ZERO,
+            lag: Duration::ZERO,
+            bounds,
+            rng: ChaCha8Rng::from_entropy(),
+            current_level: 1,
+            bonus_life_awarded: false,
+            player_shots: 0,
+            player_died: false,
+            player_died_timer: Timer::new(PLAYER_DIED_DURATION),
+            game_over_timer: Timer::new(GAME_OVER_DURATION),
+            next_level_timer: Timer::new(NEXT_LEVEL_DURATION),
+            ufo_timer: Timer::new(Duration::from_secs(MAX_UFO_TIMER_DURATION)),
+            game_over: GameOverProgress::new(),
+            march_note: 0,
+            exploding_alien: None,
+            alien_explosion_timer: Timer::new(ALIEN_EXPLOSION_DURATION),
+            alien_swarm_direction,
+            alien_swarm_speed: ALIEN_SWARM_INITIAL_SPEED,
+            alien_speed: ALIEN_INITIAL_SPEED,
+            swarm_cursor: 0,
+            swarm_step_down: 0,
+            swarm_reverse: false,
+            alien_dead: 0,
+            swarm: SwarmGrid::new(
+                NUMBER_ALIEN_COLUMNS,
+                NUMBER_ALIEN_ROWS,
+                alien_swarm_top_left_position,
+                Size::new(ALIEN_SPACING_HORZ, ALIEN_SPACING_VERT),
+                aliens),
+            next_alien_bullet_time: Timer::new(ALIEN_BULLET_START_DURATION),
+            next_alien_bullet_type: AlienBulletType::Plunger,
+            animate_alien_bullet_time: Timer::new(ANIMATE_ALIEN_BULLET_DURATION),
+            entities,
+            player,
+            alien_bullets,
+            events: vec![],
+            ship,
+        }
+    }
+
+    /// move the game's clock forward
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - Time passed since the clock was last moved
+    #[inline]
+    pub fn advance_time(&mut self, elapsed: Duration) {
+        self.time += elapsed;
+    }
+
+    /// the swarm has stepped, raising a march step event with the next note
+    pub fn step_march(&mut self) {
+        let note = self.march_note;
+        self.march_note = (self.march_note + 1) % MARCH_NOTES;
+        self.push_event(GameEvent::MarchStep { note });
+    }
+
+    /// start the march from its first note
+    pub fn reset_march(&mut self) {
+        self.march_note = 0;
+    }
+
+    /// insert a coin, adding credits once enough coins have been inserted
+    pub fn insert_coin(&mut self) {
+        self.coins += 1;
+        if self.coins >= self.coinage.coins() {
+            self.coins = 0;
+            // as per the original, credits are limited to two digits
+            self.credits = (self.credits + self.coinage.credits()).min(MAX_CREDITS);
+        }
+    }
+
+    /// use a credit to start a game, returns true if the game can be started
+    pub fn use_credit(&mut self) -> bool {
+        if self.free_play {
+            return true;
+        }
+        if self.credits > 0 {
+            self.credits -= 1;
+            return true;
+        }
+        false
+    }
+
+    /// returns the points awarded for hitting the UFO now, following the original's table, which goes by the
+    /// number of shots fired, when enabled
+    pub fn get_ufo_points(&self) -> i32 {
+        if self.arcade_ufo {
+            UFO_POINTS_TABLE[self.player_shots as usize % UFO_POINTS_TABLE.len()]
+        }
+        else {
+            UFO_DEFAULT_POINTS
+        }
+    }
+
+    /// returns the player's current score
+    pub fn get_player_score(&self) -> i32 {
+        if let Some(player) = self.get_entity(self.player) {
+            return player.score;
+        }
+        0
+    }
+
+    #[inline]
+    pub fn current_level_inc(&mut self) {
+        self.current_level += 1;
+    }
+
+    /// generate a random column index, from the columns that contain a live alien, if there are any
+    pub fn gen_rand_live_column(&mut self) -> Option<usize> {
+        let columns = self.swarm.live_columns().count();
+        if columns == 0 {
+            return None;
+        }
+        let n = self.rng.next_u64() as usize % columns;
+        self.swarm.live_columns().nth(n)
+    }
+
+    /// generate a random number within a range [start,end)
+    fn get_ufo_duration(&mut self) -> Duration {
+        Duration::from_secs(self.rng.gen_range(MIN_UFO_TIMER_DURATION..=MAX_UFO_TIMER_DURATION))
+    }
+
+    #[inline]
+    pub fn has_player_died_timer_expired(&self) -> bool {
+        self.player_died_timer.has_expired(self.time)
+    }
+
+    /// returns how far through the delay after the player died the game is, from 0 to 1
+    #[inline]
+    pub fn get_player_died_progress(&self) -> f32 {
+        self.player_died_timer.progress(self.time)
+    }
+
+    #[inline]
+    pub fn reset_player_died_timer(&mut self) {
+        self.player_died_timer.reset(self.time)
+    }
+
+    #[inline]
+    pub fn has_game_over_timer_expired(&self) -> bool {
+        //self.game_over_timer.has_expired()
+        self.game_over.end(self.time)
+    }
+
+    #[inline]
+    pub fn game_over_next(&mut self) {
+        self.game_over.next(self.time);
+    }
+
+    #[inline]
+    pub fn reset_game_over_timer(&mut self) {
+        //self.game_over_timer.reset();
+        self.game_over.start(self.time);
+    }
+
+    /// returns how much of the game over text is displayed
+    #[inline]
+    pub fn get_game_over(&self) -> &GameOverProgress {
+        &self.game_over
+    }
+
+    #[inline]
+    pub fn has_next_level_timer_expired(&self) -> bool {
+        self.game_over_timer.has_expired(self.time)
+    }
+
+    #[inline]
+    pub fn reset_next_level_timer(&mut self) {
+        self.game_over_timer.reset(self.time)
+    }
+
+    #[inline]
+    pub fn has_ufo_timer_expired(&self) -> bool {
+        self.ufo_timer.has_expired(self.time)
+    }
+
+    #[inline]
+    pub fn reset_ufo_timer(&mut self) {
+        // pick a new duration for our ufo to appear, which is fixed in the original
+        let d = if self.arcade_ufo { UFO_ARCADE_TIMER_DURATION } else { self.get_ufo_duration() };
+        self.ufo_timer.set_duration(d);
+        self.ufo_timer.reset(self.time);
+    }
+
+    /// returns elasped time since the last call
+    #[inline]
+    pub fn get_lag(&self) -> Duration {
+        self.lag
+    }
+
+    #[inline]
+    pub fn reset_lag(&mut self) {
+        self.lag = Duration::new(0,0);
+    }
+
+    /// returns the row of the swarm an alien is in, 0 being the top row
+    #[inline]
+    pub fn get_alien_row(&self, index: usize) -> usize {
+        self.swarm.row(index)
+    }
+
+    /// count a killed alien, once its explosion has finished, it was removed from the swarm's grid when hit
+    #[inline]
+    pub fn kill_alien(&mut self) {
+        // increment the global number of alien dead
+        self.inc_alien_dead();
+    }
+
+    /// find the lowest alien, for a given column, that is alive
+    #[inline]
+    pub fn lowest_alive_alien_in_column(&self, column: usize) -> Option<Handle<Alien>> {
+        self.swarm.lowest_in_column(column).map(|index| self.get_alien(index))
+    }
+
+    /// returns the grid of the alien swarm
+    #[inline]
+    pub fn get_swarm(&self) -> &SwarmGrid {
+        &self.swarm
+    }
+
+    /// returns a mutable reference to the grid of the alien swarm
+    #[inline]
+    pub fn get_mut_swarm(&mut self) -> &mut SwarmGrid {
+        &mut self.swarm
+    }
+
+    /// start an alien exploding, freezing the swarm until it has finished
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of alien within swarm
+    pub fn start_alien_explosion(&mut self, index: usize) {
+        // it is no longer part of the swarm, so cannot be hit again or fire
+        self.swarm.kill(index);
+        self.exploding_alien = Some(index);
+        self.alien_explosion_timer.reset(self.time);
+    }
+
+    /// finish the current alien explosion, the swarm is no longer frozen
+    #[inline]
+    pub fn end_alien_explosion(&mut self) {
+        self.exploding_alien = None;
+    }
+
+    #[inline]
+    pub fn has_alien_explosion_timer_expired(&self) -> bool {
+        self.alien_explosion_timer.has_expired(self.time)
+    }
+
+    /// returns the current speed of a players bullet (i.e. number of pixels it moves per animation)
+    #[inline]
+    pub fn get_player_bullet_speed(&self) -> i32 {
+        PLAYER_BULLET_SPEED
+    }
+
+    /// returns the number of pixels an alien moves down when the swarm reaches a boundary
+    #[inline]
+    pub fn get_alien_step_down(&self) -> u32 {
+        ALIEN_STEP_DOWN
+    }
+
+    /// returns the amount of time a bullet explosion appears on the screen
+    #[inline]
+    pub fn get_bullet_explosion_time(&self) -> u64 {
+        BULLET_EXPLOSION_TIME
+    }
+
+    /// returns the amount of time a ufo explosion appears on the screen
+    #[inline]
+    pub fn get_ufo_explosion_time(&self) -> u64 {
+        UFO_EXPLOSION_TIME
+    }
+
+    /// returns the amount of time the points awarded for the ufo appear on the screen
+    #[inline]
+    pub fn get_ufo_points_time(&self) -> u64 {
+        UFO_POINTS_TIME
+    }
+
+    /// retuns the number of columns in the alien swarm
+    #[inline]
+    pub fn get_number_alien_columns(&self) -> usize {
+        self.swarm.get_number_columns()
+    }
+
+    #[inline]
+    pub fn get_mut_alien_swarm_top_left_postion(&mut self) -> &mut Point {
+        self.swarm.get_mut_origin()
+    }
+
+    /// returns the time between single alien moves, when using faithful swarm movement
+    #[inline]
+    pub fn get_alien_tick_duration(&self) -> Duration {
+        ALIEN_FAITHFUL_TICK_DURATION
+    }
+
+    /// returns the number of pixels a single alien moves, when using faithful swarm movement
+    ///
+    /// As in the original, the last alien moves faster to the right than it does to the left
+    #[inline]
+    pub fn get_faithful_alien_speed(&self, direction: i32) -> i32 {
+        if self.alien_dead == self.get_number_aliens() as i32 - 1 && direction == 1 {
+            ALIEN_FAITHFUL_LAST_ALIEN_RIGHT_SPEED
+        }
+        else {
+            ALIEN_FAITHFUL_SPEED
+        }
+    }
+
+    /// returns the alien index for a given position within the swarm movement order
+    ///
+    /// As in the original, aliens are moved from the bottom left, along each row, and then up a row
+    #[inline]
+    pub fn get_swarm_order_alien(&self, cursor: usize) -> usize {
+        let columns = self.swarm.get_number_columns();
+        let row = self.swarm.get_number_rows() - 1 - cursor / columns;
+        self.swarm.cell(cursor % columns, row)
+    }
+
+    /// increment the number of aliens that have been killed
+    #[inline]
+    fn inc_alien_dead(&mut self) {
+        self.alien_dead += 1;
+    }
+
+    #[inline]
+    pub fn reset_alien_dead(&mut self) {
+        self.alien_dead = 0;
+    }
+
+    /// returns the internal screen bounds, i.e. the bounding box of the playable area
+    #[inline]
+    pub fn get_bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// add a live explosion, i.e. a BulletExplosion or UfoExplosion
+    pub fn add_explosion<T: EntityKind>(&mut self, explosion: T) -> Handle<T> {
+        self.entities.spawn(explosion)
+    }
+
+    /// delete an explosion, thus it will no longer be live
+    pub fn delete_explosion<T: EntityKind>(&mut self, explosion: Handle<T>) {
+        self.entities.despawn(explosion);
+    }
+
+    /// raise an event, which is available to other systems until the end of the frame
+    #[inline]
+    pub fn push_event(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// iterator over events raised during the current frame
+    #[inline]
+    pub fn get_events(&'_ self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.iter().cloned()
+    }
+
+    /// returns the handle for the player
+    // TODO: add a 2nd player
+    #[inline]
+    pub fn get_player(&self) -> Handle<Player> {
+        self.player
+    }
+
+    /// returns the handle for the ship
+    #[inline]
+    pub fn get_ship(&self) -> Handle<Ship> {
+        self.ship
+    }
+
+    /// returns an entity, which might not exist anymore so it is wrapped in Option
+    #[inline]
+    pub fn get_entity<T: EntityKind>(&self, handle: Handle<T>) -> Option<&T> {
+        self.entities.get(handle)
+    }
+
+    /// returns the store of all entities, e.g. to iterate over a kind of entity
+    #[inline]
+    pub fn get_entities(&self) -> &EntityStore {
+        &self.entities
+    }
+
+    /// returns the number of aliens in the swarm, alive or not
+    #[inline]
+    pub fn get_number_aliens(&self) -> usize {
+        self.swarm.get_number_cells()
+    }
+
+    /// returns the handle for the alien in a cell of the swarm
+    #[inline]
+    pub fn get_alien(&self, index: usize) -> Handle<Alien> {
+        self.swarm.get_alien(index)
+    }
+
+    /// returns the handle for an alien bullet
+    #[inline]
+    pub fn get_alien_bullet(&self, bullet_type: AlienBulletType) -> Handle<Bullet> {
+        self.alien_bullets[bullet_type as usize]
+    }
+
+    /// returns the handles for the alien bullets
+    #[inline]
+    pub fn get_alien_bullets(&self) -> [Handle<Bullet>;3] {
+        self.alien_bullets
+    }
+
+    #[inline]
+    pub fn inc_next_bullet_type(&mut self) {
+        self.next_alien_bullet_type = self.next_alien_bullet_type.next();
+    }
+
+
+    #[inline]
+    pub fn has_alien_bullet_timer_expired(&self) -> bool {
+        self.next_alien_bullet_time.has_expired(self.time)
+    }
+
+    /// reset alien bullet timer, when the original's reload rules are enabled aliens reload faster as
+    /// the player's score increases
+    #[inline]
+    pub fn reset_alien_bullet_timer(&mut self) {
+        let duration = if self.arcade_alien_reload {
+            let score = self.get_player_score() as u32;
+            ALIEN_BULLET_ARCADE_RELOAD
+                .iter()
+                .find(|(below, _)| score < *below)
+                .map_or(ALIEN_BULLET_ARCADE_MIN_RELOAD, |(_, duration)| *duration)
+        }
+        else {
+            ALIEN_BULLET_START_DURATION
+        };
+        self.next_alien_bullet_time.set_duration(duration);
+        self.next_alien_bullet_time.reset(self.time)
+    }
+
+    #[inline]
+    pub fn has_animate_alien_bullet_timer_expired(&self) -> bool {
+        self.animate_alien_bullet_time.has_expired(self.time)
+    }
+
+    #[inline]
+    pub fn reset_animate_alien_bullet_timer(&mut self) {
+        self.animate_alien_bullet_time.reset(self.time)
+    }
+
+    /// returns a mutable reference to an entity, which might not exist anymore so it is wrapped in Option
+    #[inline]
+    pub fn get_mut_entity<T: EntityKind>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.entities.get_mut(handle)
+    }
+
+    /// returns a mutable reference to the store of all entities
+    #[inline]
+    pub fn get_mut_entities(&mut self) -> &mut EntityStore {
+        &mut self.entities
+    }
+
+    // update the game, needs to be called each time around the main loop
+    // to keep everything moving forward
+    pub fn update(&mut self) {
+        // handle any cleanup needed in the game and so on, events have been handled by now
+        self.events.clear();
+
+        let elasped_time = self.time - self.previous_time;
+        self.previous_time = self.time;
+        self.lag += elasped_time;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::asset::AssetReport;
+    use crate::asset_names::*;
+    use crate::assets::Assets;
+    use crate::sprite_sheet::{SheetJSON, AnimationJSON, SpriteSheet};
+    use crate::controls::*;
+    use crate::collision::*;
+
+    /// returns the assets, and the state of a game that has just started, loaded from the asset root
+    pub fn new_game_state() -> (Assets, GameState) {
+        let mut report = AssetReport::new();
+        let sheet_json = report.check(SheetJSON::new(SHEET_JSON));
+        let anis_json = report.check(AnimationJSON::from_json(ANIMATIONS_JSON));
+        let sprite_sheet = report.check(SpriteSheet::new(SHEET_IMAGE));
+        let (assets, mut state) = 
+            initial_game_state(&sheet_json, &anis_json, &sprite_sheet, 0, PLAYER_START_LIVES, &mut report);
+        assert!(report.is_empty(), "{}", report);
+
+        state.mode = GameMode::Playing;
+        state.reset_ufo_timer();
+        (assets, state)
+    }
+
+    /// play a frame of the game, as the main loop does
+    pub fn play_frame(assets: &Assets, state: &mut GameState, controls: Controls) {
+        state.advance_time(Duration::from_millis(16));
+        player_control_system(assets, state, Some(controls));
+        bullet_control_system(state);
+        alien_control_system(state);
+        ship_control_system(state);
+        bullet_collision_system(assets, state);
+        state.update();
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let (assets, mut state) = new_game_state();
+
+        // hit the bottom left alien, and play on with the player firing whenever they can
+        let alien = state.get_swarm().lowest_in_column(0).unwrap();
+        state.start_alien_explosion(alien);
+        for _ in 0..200 {
+            play_frame(&assets, &mut state, Controls { fire: true, ..Controls::default() });
+        }
+        assert!(state.alien_dead > 0);
+        assert!(state.get_swarm().lowest_in_column(0) != Some(alien));
+
+        let snapshot = serde_json::to_string(&state).unwrap();
+        let mut restored: GameState = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(restored, state);
+
+        // the restored game carries on exactly as the original, random choices included
+        for _ in 0..200 {
+            play_frame(&assets, &mut state, Controls::default());
+            play_frame(&assets, &mut restored, Controls::default());
+        }
+        assert_eq!(restored, state);
+    }
+
+    #[test]
+    fn march_notes_cycle() {
+        let (_, mut state) = new_game_state();
+        state.events.clear();
+
+        for _ in 0..MARCH_NOTES + 2 {
+            state.step_march();
+        }
+        let notes: Vec<usize> = state.get_events().filter_map(|event| match event {
+            GameEvent::MarchStep { note } => Some(note),
+            _ => None,
+        }).collect();
+        assert_eq!(notes, vec![0, 1, 2, 3, 0, 1]);
+
+        // the next wave starts the march from its first note
+        state.reset_march();
+        state.step_march();
+        assert_eq!(state.get_events().last(), Some(GameEvent::MarchStep { note: 0 }));
+    }
+}
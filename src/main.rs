@@ -42,6 +42,9 @@ extern crate line_drawing;
 extern crate rand;
 
 mod sprite_sheet;
+mod sprite_sheet_json;
+mod asset;
+mod asset_names;
 mod frame;
 
 mod controls;
@@ -54,6 +57,10 @@ mod renderer;
 use crate::renderer::*;
 
 mod entity;
+mod entity_store;
+mod assets;
+mod game_state;
+use crate::game_state::*;
 
 mod world;
 use crate::world::*;
@@ -63,6 +70,7 @@ use crate::audio::*;
 
 mod math;
 mod animation;
+mod swarm;
 
 mod collision;
 use crate::collision::*;
@@ -70,7 +78,17 @@ use crate::collision::*;
 mod timer;
 mod text;
 mod sound;
+mod sound_pack;
+mod sound_pack_json;
+mod sound_check;
+mod audio_backend;
+mod synth;
+mod mixer;
 mod config;
+mod settings;
+mod event;
+use crate::event::*;
+use crate::settings::*;
 
 use config::*;
 
@@ -80,35 +98,57 @@ use config::*;
 fn main() {
     env_logger::init();
 
-    // create the hardware interface ... wgpu/pixels on desktop and 32bit for STM hardware (TODO)
-    let (event_loop, mut interface) = create_interface("Space Invaders");
-    
+    // options that take a value are an error without one, rather than being silently ignored
+    if let Err(error) = check_command_line(&[asset::ASSETS_ARG, audio_backend::EXPORT_AUDIO_ARG]) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
     // load config
     let mut config = Config::new();
 
-    // create the initial state of the game world
-    let mut world = initial_world_state(&config);
+    // create the initial state of the game world, reporting any assets that could not be loaded
+    let mut world = match initial_world_state(&config) {
+        Ok(world) => world,
+        Err(report) => {
+            eprint!("{}", report);
+            std::process::exit(1);
+        },
+    };
+
+    // create the hardware interface ... wgpu/pixels on desktop and 32bit for STM hardware (TODO)
+    let (event_loop, mut interface) = create_interface("Space Invaders");
     
     // enter game loop
     event_loop.run(move |event, _, control_flow| {
-        let current_state = world.get_current_state();
+        // the game's clock follows the wall clock
+        world.update_time();
+
+        let current_state = world.get_state().mode;
+
+        // each frame drawn is a game tick
+        let tick = interface.render(&event);
 
         // do we need to update the display
-        if interface.render(&event) {
+        if tick {
             // begin rendering, need by some backends
             interface.begin_draw();
             
             // render game if playing or paused
-            if  current_state == GameState::Playing || current_state == GameState::Paused {
-                renderer_system(&world, &mut interface);
+            if  current_state == GameMode::Playing || current_state == GameMode::Paused {
+                renderer_system(world.get_assets(), world.get_state(), &mut interface);
             }
             // should we display the gameover message
-            else if current_state == GameState::GameOver {
-                renderer_gameover(&world, &mut interface);
+            else if current_state == GameMode::GameOver {
+                renderer_gameover(world.get_assets(), world.get_state(), &mut interface);
             }
             // or otherwise might be the splash screen
-            else if current_state == GameState::Splash {
-                renderer_splash(&world, &mut interface);
+            else if current_state == GameMode::Splash {
+                renderer_splash(world.get_assets(), &mut interface);
+            }
+            // or the operator settings screen
+            else if current_state == GameMode::Settings {
+                renderer_settings(&world, &mut interface);
             }
 
             // end redering, need to close drawing surfaces on some backends
@@ -120,67 +160,99 @@ fn main() {
         // check if we should quit and exit if requested
         if should_exit {
             // fetch and store high score for next play
-            config.udpate_highscore(world.get_high_score());
+            config.udpate_highscore(world.get_state().high_score);
             config.store();
+            if let Err(e) = world.finish_sound() {
+                log::warn!("unable to finish audio: {}", e);
+            }
             *control_flow = ControlFlow::Exit;
             return;
         }
         
+        // coins can be inserted at any time, except when editing settings
+        if let Some(control) = &controls {
+            if control.coin && current_state != GameMode::Settings {
+                world.get_mut_state().insert_coin();
+            }
+            // sound can be muted at any time
+            if control.mute {
+                world.toggle_mute();
+            }
+            if control.mute_music {
+                world.toggle_music_mute();
+            }
+        }
+        
+        // audio backends that do not play through a device need updating every game tick, this is done 
+        // before this tick's sounds are played, so that they start at the tick's time in the mix
+        if tick {
+            world.update_sound();
+        }
+
         // handle the state when game is in full swing
-        if  current_state == GameState::Playing {
-            world.play_music(world.get_current_bpm());
+        if  current_state == GameMode::Playing {
+            let (assets, state) = world.get_assets_and_mut_state();
+
             // handle updates for player, alien, and ship components
-            player_control_system(&mut world, controls);
+            player_control_system(assets, state, controls);
             // handle movment update for all types of bullets
-            bullet_control_system(&mut world);
+            bullet_control_system(state);
             // handle movement updates for aliens
-            alien_control_system(&mut world);
+            alien_control_system(state);
             // handle movment of UFO
-            ship_control_system(&mut world);
+            ship_control_system(state);
 
             // handle bullet collisons, possible end game state reached on return...
-            bullet_collision_system(&mut world);
+            bullet_collision_system(assets, state);
 
             // handle the audio system
             audio_system(&world);
+            // log this frame's events
+            event_log_system(world.get_state());
 
-            // finally update the world to handle any internal changes
-            world.update();
+            // finally update the game to handle any internal changes
+            world.get_mut_state().update();
         }
-        else if current_state == GameState::Splash {
+        else if current_state == GameMode::Splash {
             // showing spash screen and fire (space) is pressed
             if let Some(control) = controls {
-                // start game
-                if control.fire {
-                    world.set_current_state(GameState::Playing);
+                // enter operator settings
+                if control.service {
+                    *world.get_mut_settings() = Settings::new(&config);
+                    world.get_mut_state().mode = GameMode::Settings;
+                }
+                // start game, if there is a credit or free play
+                else if control.fire && world.get_mut_state().use_credit() {
+                    world.get_mut_state().mode = GameMode::Playing;
                     // initalize ufo timer to something random
-                    world.reset_ufo_timer();
+                    world.get_mut_state().reset_ufo_timer();
                 }
             }
         }
+        else if current_state == GameMode::Settings {
+            settings_control_system(&mut world, &mut config, controls);
+        }
 
         // game over? 
-        if current_state == GameState::GameOver {
+        if current_state == GameMode::GameOver {
             // pause any sounds that might be playing
-            world.pause_music();
             world.pause_ufo();
             
             // is it time to move on?
-            if world.has_game_over_timer_expired() {
-                world.set_current_state(GameState::Splash);
-                new_game(&mut world);
+            let (assets, state) = world.get_assets_and_mut_state();
+            if state.has_game_over_timer_expired() {
+                state.mode = GameMode::Splash;
+                new_game(assets, state);
             }
             else {
-                world.game_over_next();
+                state.game_over_next();
             }
         }
         //move on to next level?
-        else if current_state == GameState::NextLevel {
-            world.pause_music();
-            if world.has_next_level_timer_expired() {
-                next_level(&mut world);
-                world.set_current_state(GameState::Playing);
-            }
+        else if current_state == GameMode::NextLevel && world.get_state().has_next_level_timer_expired() {
+            let (assets, state) = world.get_assets_and_mut_state();
+            next_level(assets, state);
+            state.mode = GameMode::Playing;
         }
 
         interface.request_redraw();
@@ -0,0 +1,354 @@
+//! Description:
+//!
+//! Mixer, with a master bus and separate buses for the march and sound effects, each with its own
+//! level, along with mute toggles. All sinks are mixed into a single stream, which passes through a
+//! limiter so that overlapping effects, e.g. explosions, do not clip.
+//!
+//! Effects can be panned, across the stereo mix, to follow the position of what made them.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use rodio::Source;
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::source::Zero;
+
+/// level above which the limiter reduces gain
+const LIMITER_THRESHOLD: f32 = 0.9;
+/// amount gain recovers each sample, once the level has dropped, about 0.1s to fully recover
+const LIMITER_RELEASE: f32 = 0.0001;
+
+/// number of channels all sinks are mixed to
+pub const MIX_CHANNELS: u16 = 2;
+/// sample rate all sinks are mixed at
+pub const MIX_SAMPLE_RATE: u32 = 44100;
+
+/// smallest change in a bus level
+pub const LEVEL_STEP: f32 = 0.1;
+
+/// a group of sinks sharing a level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bus {
+    /// alien march
+    Music,
+    /// all other sound effects
+    Effects,
+}
+
+/// levels for each bus, from 0 (silent) to 1 (full)
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    master: f32,
+    music: f32,
+    effects: f32,
+    /// are all buses muted
+    muted: bool,
+    /// is the music bus muted
+    music_muted: bool,
+}
+
+impl Mixer {
+    /// create a mixer with given levels, which are clamped to [0,1]
+    ///
+    /// # Arguments
+    ///
+    /// * `master` - Level of all buses
+    /// * `music` - Level of music bus
+    /// * `effects` - Level of effects bus
+    pub fn new(master: f32, music: f32, effects: f32) -> Self {
+        Mixer {
+            master: master.clamp(0.0, 1.0),
+            music: music.clamp(0.0, 1.0),
+            effects: effects.clamp(0.0, 1.0),
+            muted: false,
+            music_muted: false,
+        }
+    }
+
+    /// set the level of every bus, each clamped to [0,1]
+    pub fn set_levels(&mut self, master: f32, music: f32, effects: f32) {
+        self.master = master.clamp(0.0, 1.0);
+        self.music = music.clamp(0.0, 1.0);
+        self.effects = effects.clamp(0.0, 1.0);
+    }
+
+    /// toggle mute for all buses
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    /// toggle mute for the music bus
+    pub fn toggle_music_mute(&mut self) {
+        self.music_muted = !self.music_muted;
+    }
+
+    /// returns the overall gain of a bus, including master level and mutes
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - Bus to return gain for
+    pub fn gain(&self, bus: Bus) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+
+        let level = match bus {
+            Bus::Music if self.music_muted => 0.0,
+            Bus::Music => self.music,
+            Bus::Effects => self.effects,
+        };
+        level * self.master
+    }
+}
+
+/// peak limiter, reduces gain instantly when a sample would go above the threshold and then slowly
+/// recovers
+pub struct Limiter<S> {
+    input: S,
+    gain: f32,
+}
+
+impl<S> Limiter<S> {
+    /// create a limiter
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Source to be limited
+    pub fn new(input: S) -> Self {
+        Limiter {
+            input,
+            gain: 1.0,
+        }
+    }
+}
+
+impl<S> Iterator for Limiter<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+
+        let peak = sample.abs();
+        if peak * self.gain > LIMITER_THRESHOLD {
+            self.gain = LIMITER_THRESHOLD / peak;
+        }
+        let limited = sample * self.gain;
+        self.gain = (self.gain + LIMITER_RELEASE).min(1.0);
+
+        Some(limited)
+    }
+}
+
+impl<S> Source for Limiter<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// stereo position of a source, which can be changed while it plays, from -1 (left) through 0 (centre)
+/// to 1 (right)
+#[derive(Debug, Clone, Default)]
+pub struct PanControl(Arc<AtomicU32>);
+
+impl PanControl {
+    /// create a pan control
+    ///
+    /// # Arguments
+    ///
+    /// * `pan` - Initial position, clamped to [-1,1]
+    pub fn new(pan: f32) -> Self {
+        let control = PanControl::default();
+        control.set(pan);
+        control
+    }
+
+    /// set position, clamped to [-1,1]
+    pub fn set(&self, pan: f32) {
+        self.0.store(pan.clamp(-1.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// returns the current position
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// pans a source across the stereo mix, the source is mixed down to mono first
+///
+/// The centre is at full level in both channels, moving to one side fades out the other channel, so
+/// that a centred source sounds the same as it would without panning.
+pub struct Pan<S> {
+    input: S,
+    control: PanControl,
+    /// right channel of the current frame, still to be returned
+    right: Option<f32>,
+}
+
+impl<S> Pan<S> {
+    /// create a panned source
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Source to be panned
+    /// * `control` - Position of source
+    pub fn new(input: S, control: PanControl) -> Self {
+        Pan {
+            input,
+            control,
+            right: None,
+        }
+    }
+}
+
+impl<S> Iterator for Pan<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+
+        // mix the input's frame down to mono
+        let channels = self.input.channels().max(1);
+        let mut sample = self.input.next()?;
+        for _ in 1..channels {
+            sample += self.input.next().unwrap_or(0.0);
+        }
+        sample /= channels as f32;
+
+        let pan = self.control.get();
+        self.right = Some(sample * (1.0 + pan).min(1.0));
+        Some(sample * (1.0 - pan).min(1.0))
+    }
+}
+
+impl<S> Source for Pan<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.input.channels().max(1) as usize;
+        self.input.current_frame_len().map(|len| len / channels * 2)
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// cuts short the sources it controls, e.g. so that a new note can start straight away, rather than waiting
+/// for the one playing to finish
+#[derive(Debug, Clone, Default)]
+pub struct CutControl(Arc<AtomicU32>);
+
+impl CutControl {
+    /// cut short every source created with this control so far
+    pub fn cut(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// returns the number of cuts so far
+    fn cuts(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// a source that ends as soon as its control is cut
+pub struct Cut<S> {
+    input: S,
+    control: CutControl,
+    /// number of cuts when source was created, any more and it has been cut
+    cuts: u32,
+}
+
+impl<S> Cut<S> {
+    /// create a source that can be cut short
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Source to play until cut
+    /// * `control` - Control that cuts the source
+    pub fn new(input: S, control: CutControl) -> Self {
+        Cut {
+            input,
+            cuts: control.cuts(),
+            control,
+        }
+    }
+}
+
+impl<S> Iterator for Cut<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.control.cuts() != self.cuts {
+            return None;
+        }
+        self.input.next()
+    }
+}
+
+impl<S> Source for Cut<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// create a mix, sources added to the returned controller are mixed into the returned, limited, output
+/// which plays forever
+pub fn limited_mix() -> (Arc<DynamicMixerController<f32>>, Limiter<DynamicMixer<f32>>) {
+    let (controller, mix) = dynamic_mixer::mixer(MIX_CHANNELS, MIX_SAMPLE_RATE);
+    // the mix ends when it has no sources, so make sure it always has one
+    controller.add(Zero::<f32>::new(MIX_CHANNELS, MIX_SAMPLE_RATE));
+    (controller, Limiter::new(mix))
+}
@@ -14,37 +14,83 @@ use crate::frame::*;
 use crate::interface::*;
 use crate::entity::*;
 use crate::world::*;
+use crate::game_state::*;
+use crate::assets::Assets;
 use crate::math::*;
 use crate::text::*;
+use crate::sprite_sheet::{BlendMode, DrawStyle};
+
+/// colour the screen flashes when the player dies
+const PLAYER_DIED_FLASH_COLOUR: [u8; 4] = [0xFF, 0x00, 0x00, 0xFF];
+/// opacity of the flash when the player dies, it then fades out until play continues
+const PLAYER_DIED_FLASH_OPACITY: f32 = 0.6;
+/// explosions are added to what is behind them, so that they glow
+const EXPLOSION_STYLE: DrawStyle = DrawStyle { blend: BlendMode::Add, tint: [0xFF; 4], opacity: 1.0 };
 
 /// draw the splash screen
-pub fn renderer_splash(world: &World, interface: &mut Interface) {
+pub fn renderer_splash(assets: &Assets, interface: &mut Interface) {
     // we don't really need this as it is a full screen splash, but anyway
 
     let mut frame = interface.framebuffer();
 
-    let sheet = world.get_sprite_sheet();
 
     // draw the splash sprite to the framebuffer
-    world.get_splash_screen_sprite().render(0, 0, sheet, &mut frame);
+    assets.get_splash_screen_sprite().render(0, 0, &mut frame);
 
     interface.draw_call();
 }
 
 /// draw the gameover screen
-pub fn renderer_gameover(world: &World, interface: &mut Interface) {
+pub fn renderer_gameover(assets: &Assets, state: &GameState, interface: &mut Interface) {
+
+    let mut frame = interface.framebuffer();
+
+    assets.get_game_over().render(
+        state.get_game_over(), Point::new(GAMEOVER_X_POSITION, GAMEOVER_Y_POSITION), &mut frame);
+
+    interface.draw_call();
+}
+
+/// draw the operator settings screen
+pub fn renderer_settings(world: &World, interface: &mut Interface) {
+
+    interface.clear_framebuffer([0x0,0x0,0x0,0xFF]);
 
     let mut frame = interface.framebuffer();
-    // get ref to sprite sheet used to render sprites and animations
-    let sheet = world.get_sprite_sheet();
 
-    world.get_game_over().render(Point::new(GAMEOVER_X_POSITION, GAMEOVER_Y_POSITION), sheet, &mut frame);
+    let white = [0xFF, 0xFF, 0xFF, 0xFF];
+    let green = [0x28, 0xcf, 0x28, 0xFF];
+
+    render_system_text("SETTINGS", Point::new(SETTINGS_X_POSITION, SETTINGS_Y_POSITION), green, &mut frame);
+
+    let settings = world.get_settings();
+    let lines = settings.lines(world.get_state().high_score);
+    for (i, (label, value)) in lines.iter().enumerate() {
+        let y = SETTINGS_Y_POSITION + SETTINGS_LINE_SPACING * (i as u32 + 2);
+        let colour = if i == settings.get_selected() { green } else { white };
+        if i == settings.get_selected() {
+            render_system_text(">", Point::new(SETTINGS_X_POSITION - SETTINGS_CURSOR_OFFSET, y), colour, &mut frame);
+        }
+        render_system_text(label, Point::new(SETTINGS_X_POSITION, y), colour, &mut frame);
+        render_system_text(value, Point::new(SETTINGS_VALUE_X_POSITION, y), colour, &mut frame);
+    }
+
+    render_system_text(
+        "UP DOWN SELECT  LEFT RIGHT CHANGE",
+        Point::new(SETTINGS_X_POSITION, SETTINGS_Y_POSITION + SETTINGS_LINE_SPACING * (lines.len() as u32 + 3)), 
+        white, 
+        &mut frame);
+    render_system_text(
+        "FIRE CONFIRM  SERVICE LEAVE",
+        Point::new(SETTINGS_X_POSITION, SETTINGS_Y_POSITION + SETTINGS_LINE_SPACING * (lines.len() as u32 + 4)), 
+        white, 
+        &mut frame);
 
     interface.draw_call();
 }
 
 /// render the game frame
-pub fn renderer_system(world: &World, interface: &mut Interface) {
+pub fn renderer_system(assets: &Assets, state: &GameState, interface: &mut Interface) {
 
     interface.clear_framebuffer([0x0,0x0,0x0,0xFF]);
         
@@ -52,133 +98,132 @@ pub fn renderer_system(world: &World, interface: &mut Interface) {
     // let mut frame = Frame::new(p, Interface::get_width(), Interface::get_height());
     let mut frame = interface.framebuffer();
 
-    // get ref to sprite sheet used to render sprites and animations
-    let sheet = world.get_sprite_sheet();
 
     // first draw the player
-    if let Some(entity) = world.get_entity(world.get_player()) {
-        if let Entity::Player(player) = entity {
-            if player.lives_remaining != 0 && !world.get_player_died() {
-                player.sprite.render(player.position.x, player.position.y, sheet, &mut frame);
-            }
+    if let Some(player) = state.get_entity(state.get_player()) {
+        if player.lives_remaining != 0 && !state.player_died {
+            assets.get_sprite(player.sprite).render(player.position.x, player.position.y, &mut frame);
+        }
 
-            // draw player bullet if in flight
-            if player.bullet.bullet_mode == BulletMode::InFlight {
-                if let Left(sprite) = player.bullet.sprite.clone() {
-                    sprite.render(player.bullet.position.x, player.bullet.position.y, sheet, &mut frame);
-                }
+        // draw player bullet if in flight
+        if player.bullet.bullet_mode == BulletMode::InFlight {
+            if let Left(sprite) = player.bullet.sprite {
+                assets.get_sprite(sprite).render(player.bullet.position.x, player.bullet.position.y, &mut frame);
             }
+        }
+
+        // draw scores
 
-            // draw scores
+        // player 1 score
+        assets.get_digits().render_num(player.score as u32, Point::new(250,30), &mut frame); 
+        assets.get_score_text().render_player1(Point::new(150,5), &mut frame);
 
-            // player 1 score
-            world.get_digits().render_num(player.score as u32, Point::new(250,30), sheet, &mut frame); 
-            world.get_score_text().render_player1(Point::new(150,5), sheet, &mut frame);
+        // high score
+        assets.get_score_text().render_hi_score(Point::new(700,5), &mut frame);
+        assets.get_digits().render_num(state.high_score, Point::new(840,30), &mut frame); 
 
-            // high score
-            world.get_score_text().render_hi_score(Point::new(700,5), sheet, &mut frame);
-            world.get_digits().render_num(world.get_high_score(), Point::new(840,30), sheet, &mut frame); 
+        // player 2 score (which as there is no player 2 at the moment ...)
+        assets.get_score_text().render_player2(Point::new(1300,5), &mut frame);
             
-            // player 2 score (which as there is no player 2 at the moment ...)
-            world.get_score_text().render_player2(Point::new(1300,5), sheet, &mut frame);
+        // draw credits
+        assets.get_score_text().render_credit(
+            Point::new(1100, PLAYER_LIVES_TOP_LEFT_Y_START_POSITION), 
+            &mut frame);
+        // credits are limited to 2 digits
+        assets.get_digits().render_string(
+            format!("{:02}", state.credits), 
+            Point::new(1500,PLAYER_LIVES_TOP_LEFT_Y_START_POSITION),
+            &mut frame); 
         
-            // draw credits
-            world.get_score_text().render_credit(
-                Point::new(1100, PLAYER_LIVES_TOP_LEFT_Y_START_POSITION), 
-                sheet, 
-                &mut frame);
-            // no real credits needed to play, so we simply draw 00
-            world.get_digits().render_string(
-                "00".to_string(), 
-                Point::new(1500,PLAYER_LIVES_TOP_LEFT_Y_START_POSITION),
-                sheet, 
-                &mut frame); 
-
-            // draw any lives left
-            world.get_digits().render(
-                player.lives_remaining as u32, 
-                Point::new(
-                    PLAYER_LIVES_TOP_LEFT_X_START_POSITION - 60,
-                    PLAYER_LIVES_TOP_LEFT_Y_START_POSITION + 2), 
-                sheet, 
+        // draw any lives left
+        assets.get_digits().render(
+            player.lives_remaining as u32, 
+            Point::new(
+                PLAYER_LIVES_TOP_LEFT_X_START_POSITION - 60,
+                PLAYER_LIVES_TOP_LEFT_Y_START_POSITION + 2), 
+            &mut frame);
+        // one icon for each life in reserve, which with a bonus life can be more than the game started with
+        let reserve_lives = (player.lives_remaining.max(1) as u32 - 1).min(PLAYER_LIVES_MAX_ICONS);
+        for i in  0..reserve_lives {
+            let sprite = assets.get_sprite(player.sprite);
+            sprite.render(
+                PLAYER_LIVES_TOP_LEFT_X_START_POSITION + (sprite.width + 100)*i, 
+                PLAYER_LIVES_TOP_LEFT_Y_START_POSITION,  
                 &mut frame);
-            for i in  0..player.lives_remaining as u32-1 {
-                player.sprite.render(
-                    PLAYER_LIVES_TOP_LEFT_X_START_POSITION + (player.sprite.width + 100)*i, 
-                    PLAYER_LIVES_TOP_LEFT_Y_START_POSITION,  
-                    sheet, 
-                    &mut frame);
-            }
         }
     }
 
     // draw barriers
-    for index in world.get_barriers() {
-        if let Some(entity) = world.get_entity(index) {
-            if let Entity::Barrier(barrier) = entity {
-                barrier.sprite.render_with_mask(
-                    barrier.position.x, 
-                    barrier.position.y, 
-                    &barrier.mask,
-                    sheet, 
-                    &mut frame);
-            }
-        }
+    for (_, barrier) in state.get_entities().iter::<Barrier>() {
+        assets.get_sprite(barrier.sprite).render_with_mask(
+            barrier.position.x, 
+            barrier.position.y, 
+            &barrier.mask,
+            &mut frame);
     }
 
     // draw aliens
-    for index in world.get_aliens() {
-        if let Some(entity) = world.get_entity(index) {
-            if let Entity::Alien(alien) = entity {
-                // only draw alive aliens
-                if alien.is_alive {
-                    alien.animation.render(alien.position, sheet, &mut frame);
-                }
-            }
+    for (_, alien) in state.get_entities().iter::<Alien>() {
+        // only draw alive aliens, or the explosion in place of an exploding alien
+        match alien.state {
+            AlienState::Alive => alien.animation.render(assets, alien.position, &mut frame),
+            AlienState::Exploding => 
+                assets.get_alien_explosion_sprite().render_with_style(
+                    alien.position.x, alien.position.y, &EXPLOSION_STYLE, &mut frame),
+            AlienState::Dead => { },
         }
     }
 
     // draw bullets
     
     // alien bullets that are in flight
-    for index in world.get_alien_bullets().iter() {
-        if let Some(entity) = world.get_entity(*index) {
-            if let Entity::Bullet(bullet) = entity {
-                if bullet.bullet_mode == BulletMode::InFlight {
-                    match bullet.sprite.clone() {
-                        Left(sprite) =>  {
-                            sprite.render(bullet.position.x, bullet.position.y, sheet, &mut frame);
-                        },
-                        Right(animation) =>  {
-                            animation.render(bullet.position, sheet, &mut frame);
-                        },
-                    }
-                }
-            }
-        }
-    }
-
-    for index in world.get_explosions() {
-        if let Some(entity) = world.get_entity(index) {
-            if let Entity::BulletExplosion(explosion) = entity {
-                match explosion.sprite.clone() {
+    for index in state.get_alien_bullets().iter() {
+        if let Some(bullet) = state.get_entity(*index) {
+            if bullet.bullet_mode == BulletMode::InFlight {
+                match &bullet.sprite {
                     Left(sprite) =>  {
-                        sprite.render(explosion.position.x, explosion.position.y, sheet, &mut frame);
+                        assets.get_sprite(*sprite).render(bullet.position.x, bullet.position.y, &mut frame);
                     },
                     Right(animation) =>  {
-                        animation.render(explosion.position, sheet, &mut frame);
+                        animation.render(assets, bullet.position, &mut frame);
                     },
                 }
             }
         }
     }
 
+    for (_, explosion) in state.get_entities().iter::<BulletExplosion>() {
+        match &explosion.sprite {
+            Left(sprite) =>  {
+                assets.get_sprite(*sprite).render_with_style(
+                    explosion.position.x, explosion.position.y, &EXPLOSION_STYLE, &mut frame);
+            },
+            Right(animation) =>  {
+                animation.render_with_style(assets, explosion.position, &EXPLOSION_STYLE, &mut frame);
+            },
+        }
+    }
+
+    for (_, explosion) in state.get_entities().iter::<UfoExplosion>() {
+        if explosion.framecount > 0 {
+            assets.get_sprite(explosion.sprite).render_with_style(
+                explosion.position.x, explosion.position.y, &EXPLOSION_STYLE, &mut frame);
+        }
+        else {
+            // points fade out as their time on screen runs out
+            let opacity = explosion.points_framecount as f32 / state.get_ufo_points_time() as f32;
+            assets.get_digits().render_string_with_style(
+                explosion.points.to_string(), 
+                explosion.position, 
+                &DrawStyle::new(BlendMode::Normal, [0xFF; 4], opacity),
+                &mut frame);
+        }
+    }
+
     // draw ship
-    if let Some(entity) = world.get_entity(world.get_ship()) {
-        if let Entity::Ship(ship) = entity {
-            if ship.is_alive {
-                ship.sprite.render(ship.position.x, ship.position.y, sheet, &mut frame);
-            }
+    if let Some(ship) = state.get_entity(state.get_ship()) {
+        if ship.is_alive {
+            assets.get_sprite(ship.sprite).render(ship.position.x, ship.position.y, &mut frame);
         }
     }
 
@@ -190,6 +235,12 @@ pub fn renderer_system(world: &World, interface: &mut Interface) {
         [0x28, 0xcf, 0x28, 0xFF], 
         &mut frame );
 
+    // flash the screen red when the player dies, fading out until play continues
+    if state.player_died {
+        let opacity = PLAYER_DIED_FLASH_OPACITY * (1.0 - state.get_player_died_progress());
+        fill_screen(PLAYER_DIED_FLASH_COLOUR, &DrawStyle::new(BlendMode::Add, [0xFF; 4], opacity), &mut frame);
+    }
+
     interface.draw_call();
 }
 
@@ -227,4 +278,13 @@ pub fn fill_rect(rect: Rect, color: [u8; 4], screen: &mut Frame) {
     for y in 0..=rect.height() {
         line(Point::new(rect.min_x(), rect.min_y() + y), Point::new(rect.max_x(), rect.min_y() + y), color, screen);
     }
-}
\ No newline at end of file
+}
+
+/// fill the whole framebuffer with a colour, drawn in a style, e.g. to flash the screen
+pub fn fill_screen(colour: [u8; 4], style: &DrawStyle, screen: &mut Frame) {
+    for y in 0..Interface::get_height() {
+        for x in 0..Interface::get_width() {
+            style.put_pixel(x * 4, y, &colour, screen);
+        }
+    }
+}
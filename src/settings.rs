@@ -0,0 +1,349 @@
+//! Description:
+//!
+//! Operator settings screen, modelled on the DIP switches of the original cabinet. It is entered from the
+//! splash screen with the service key and edits the settings stored in the configuration, which are only
+//! written once the operator chooses to save them.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use crate::config::*;
+use crate::controls::*;
+use crate::mixer::LEVEL_STEP;
+use crate::world::*;
+use crate::game_state::GameMode;
+
+/// items that can be selected on the settings screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsItem {
+    Lives,
+    BonusLife,
+    Coinage,
+    FreePlay,
+    AlienReload,
+    Ufo,
+    Swarm,
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    ResetHighScore,
+    Save,
+    Exit,
+}
+
+/// items in the order they are displayed
+const SETTINGS_ITEMS: [SettingsItem; 13] = [
+    SettingsItem::Lives,
+    SettingsItem::BonusLife,
+    SettingsItem::Coinage,
+    SettingsItem::FreePlay,
+    SettingsItem::AlienReload,
+    SettingsItem::Ufo,
+    SettingsItem::Swarm,
+    SettingsItem::MasterVolume,
+    SettingsItem::MusicVolume,
+    SettingsItem::EffectsVolume,
+    SettingsItem::ResetHighScore,
+    SettingsItem::Save,
+    SettingsItem::Exit,
+];
+
+/// settings being edited, these are a copy of the configuration until saved
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// index of currently selected item
+    selected: usize,
+    starting_lives: u32,
+    bonus_life_score: u32,
+    coinage: Coinage,
+    free_play: bool,
+    arcade_alien_reload: bool,
+    arcade_ufo: bool,
+    swarm_movement: SwarmMovement,
+    master_volume: f32,
+    music_volume: f32,
+    effects_volume: f32,
+    /// reset the high score when saved
+    reset_high_score: bool,
+}
+
+impl Settings {
+    /// create settings for editing from the current configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Game configuration
+    pub fn new(config: &Config) -> Self {
+        Settings {
+            selected: 0,
+            starting_lives: config.get_starting_lives(),
+            bonus_life_score: config.get_bonus_life_score(),
+            coinage: config.get_coinage(),
+            free_play: config.get_free_play(),
+            arcade_alien_reload: config.get_arcade_alien_reload(),
+            arcade_ufo: config.get_arcade_ufo(),
+            swarm_movement: config.get_swarm_movement(),
+            master_volume: config.get_master_volume(),
+            music_volume: config.get_music_volume(),
+            effects_volume: config.get_effects_volume(),
+            reset_high_score: false,
+        }
+    }
+
+    /// returns the index of the currently selected item
+    #[inline]
+    pub fn get_selected(&self) -> usize {
+        self.selected
+    }
+
+    /// returns the currently selected item
+    #[inline]
+    pub fn get_selected_item(&self) -> SettingsItem {
+        SETTINGS_ITEMS[self.selected]
+    }
+
+    /// select the next item, wrapping to the first
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % SETTINGS_ITEMS.len();
+    }
+
+    /// select the previous item, wrapping to the last
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + SETTINGS_ITEMS.len() - 1) % SETTINGS_ITEMS.len();
+    }
+
+    /// change the value of the selected item, only valid values can be chosen
+    ///
+    /// # Arguments
+    ///
+    /// * `forward` - move to the next value if true, otherwise the previous value
+    pub fn change(&mut self, forward: bool) {
+        match self.get_selected_item() {
+            SettingsItem::Lives => {
+                let range = MAX_STARTING_LIVES - MIN_STARTING_LIVES + 1;
+                let offset = if forward { 1 } else { range - 1 };
+                self.starting_lives = MIN_STARTING_LIVES + (self.starting_lives - MIN_STARTING_LIVES + offset) % range;
+            },
+            SettingsItem::BonusLife => {
+                let index = BONUS_LIFE_SCORES.iter().position(|s| *s == self.bonus_life_score).unwrap_or(0);
+                let offset = if forward { 1 } else { BONUS_LIFE_SCORES.len() - 1 };
+                self.bonus_life_score = BONUS_LIFE_SCORES[(index + offset) % BONUS_LIFE_SCORES.len()];
+            },
+            SettingsItem::Coinage => {
+                self.coinage = if forward { self.coinage.next() } else { self.coinage.next().next() };
+            },
+            SettingsItem::FreePlay => self.free_play = !self.free_play,
+            SettingsItem::AlienReload => self.arcade_alien_reload = !self.arcade_alien_reload,
+            SettingsItem::Ufo => self.arcade_ufo = !self.arcade_ufo,
+            SettingsItem::Swarm => {
+                self.swarm_movement = match self.swarm_movement {
+                    SwarmMovement::Timed => SwarmMovement::Faithful,
+                    SwarmMovement::Faithful => SwarmMovement::Timed,
+                };
+            },
+            SettingsItem::MasterVolume => self.master_volume = change_level(self.master_volume, forward),
+            SettingsItem::MusicVolume => self.music_volume = change_level(self.music_volume, forward),
+            SettingsItem::EffectsVolume => self.effects_volume = change_level(self.effects_volume, forward),
+            SettingsItem::ResetHighScore => self.reset_high_score = !self.reset_high_score,
+            SettingsItem::Save | SettingsItem::Exit => { },
+        }
+    }
+
+    /// returns true if the high score should be reset when saved
+    #[inline]
+    pub fn get_reset_high_score(&self) -> bool {
+        self.reset_high_score
+    }
+
+    /// returns a label and value for each item, in display order
+    ///
+    /// # Arguments
+    ///
+    /// * `high_score` - current high score, displayed alongside the reset option
+    pub fn lines(&self, high_score: u32) -> Vec<(String, String)> {
+        let on_off = |b: bool| if b { "ON".to_string() } else { "OFF".to_string() };
+        let arcade = |b: bool| if b { "ARCADE".to_string() } else { "SIMPLE".to_string() };
+        let level = |l: f32| format!("{}%", (l * 100.0).round() as u32);
+
+        SETTINGS_ITEMS.iter().map(|item| {
+            match item {
+                SettingsItem::Lives => ("LIVES".to_string(), self.starting_lives.to_string()),
+                SettingsItem::BonusLife => ("BONUS LIFE".to_string(), self.bonus_life_score.to_string()),
+                SettingsItem::Coinage => {
+                    let coinage = match self.coinage {
+                        Coinage::OneCoinOneCredit => "1 COIN 1 CREDIT",
+                        Coinage::OneCoinTwoCredits => "1 COIN 2 CREDITS",
+                        Coinage::TwoCoinsOneCredit => "2 COINS 1 CREDIT",
+                    };
+                    ("COINAGE".to_string(), coinage.to_string())
+                },
+                SettingsItem::FreePlay => ("FREE PLAY".to_string(), on_off(self.free_play)),
+                SettingsItem::AlienReload => ("ALIEN RELOAD".to_string(), arcade(self.arcade_alien_reload)),
+                SettingsItem::Ufo => ("UFO".to_string(), arcade(self.arcade_ufo)),
+                SettingsItem::Swarm =>
+                    ("SWARM".to_string(), arcade(self.swarm_movement == SwarmMovement::Faithful)),
+                SettingsItem::MasterVolume => ("MASTER VOLUME".to_string(), level(self.master_volume)),
+                SettingsItem::MusicVolume => ("MUSIC VOLUME".to_string(), level(self.music_volume)),
+                SettingsItem::EffectsVolume => ("EFFECTS VOLUME".to_string(), level(self.effects_volume)),
+                SettingsItem::ResetHighScore => {
+                    let score = if self.reset_high_score { 0 } else { high_score };
+                    (format!("HI-SCORE {:04}", score), if self.reset_high_score { "RESET" } else { "KEEP" }.to_string())
+                },
+                SettingsItem::Save => ("SAVE AND EXIT".to_string(), String::new()),
+                SettingsItem::Exit => ("EXIT".to_string(), String::new()),
+            }
+        }).collect()
+    }
+
+    /// write settings to configuration, returns false, leaving configuration unchanged, if any setting is invalid
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Game configuration to be updated (does not store externally)
+    pub fn store(&self, config: &mut Config) -> bool {
+        let mut updated = config.clone();
+        if self.reset_high_score {
+            updated.udpate_highscore(0);
+        }
+
+        if !updated.set_starting_lives(self.starting_lives) || !updated.set_bonus_life_score(self.bonus_life_score) {
+            return false;
+        }
+        updated.set_coinage(self.coinage);
+        updated.set_free_play(self.free_play);
+        updated.set_arcade_alien_reload(self.arcade_alien_reload);
+        updated.set_arcade_ufo(self.arcade_ufo);
+        updated.set_swarm_movement(self.swarm_movement);
+        updated.set_master_volume(self.master_volume);
+        updated.set_music_volume(self.music_volume);
+        updated.set_effects_volume(self.effects_volume);
+
+        *config = updated;
+        true
+    }
+}
+
+/// returns a level moved up or down a step, within [0,1]
+///
+/// # Arguments
+///
+/// * `level` - Current level
+/// * `forward` - Increase level if true, otherwise decrease
+fn change_level(level: f32, forward: bool) -> f32 {
+    let step = if forward { LEVEL_STEP } else { -LEVEL_STEP };
+    // round to a whole number of steps, so that repeated changes do not drift
+    (((level + step) / LEVEL_STEP).round() * LEVEL_STEP).clamp(0.0, 1.0)
+}
+
+/// settings control system, handles navigating and editing the settings screen
+///
+/// # Arguments
+///
+/// * `world` - The game world
+/// * `config` - Game configuration, which is stored externally when settings are saved
+/// * `controls` - Operator inputs
+pub fn settings_control_system(world: &mut World, config: &mut Config, controls: Option<Controls>) {
+    if let Some(controls) = controls {
+        let settings = world.get_mut_settings();
+
+        if controls.menu.up {
+            settings.select_previous();
+        }
+        else if controls.menu.down {
+            settings.select_next();
+        }
+        else if controls.menu.left {
+            settings.change(false);
+        }
+        else if controls.menu.right {
+            settings.change(true);
+        }
+        else if controls.fire {
+            match settings.get_selected_item() {
+                SettingsItem::Save => {
+                    let reset_high_score = settings.get_reset_high_score();
+                    if settings.store(config) {
+                        if reset_high_score {
+                            world.get_mut_state().high_score = 0;
+                        }
+                        config.store();
+                        world.apply_config(config);
+                        // start from a fresh game, so that new settings take effect
+                        let (assets, state) = world.get_assets_and_mut_state();
+                        new_game(assets, state);
+                        world.get_mut_state().mode = GameMode::Splash;
+                    }
+                    else {
+                        log::warn!("invalid settings, configuration not updated");
+                    }
+                },
+                SettingsItem::Exit => world.get_mut_state().mode = GameMode::Splash,
+                _ => settings.change(true),
+            }
+        }
+        // leave without saving
+        else if controls.service {
+            world.get_mut_state().mode = GameMode::Splash;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::PLAYER_START_LIVES;
+
+    /// selects an item in the settings menu
+    fn select(settings: &mut Settings, item: SettingsItem) {
+        while settings.get_selected_item() != item {
+            settings.select_next();
+        }
+    }
+
+    #[test]
+    fn changes_only_choose_valid_values() {
+        let mut settings = Settings::new(&Config::default());
+
+        select(&mut settings, SettingsItem::Lives);
+        let mut lives = vec![];
+        for _ in 0..(MAX_STARTING_LIVES - MIN_STARTING_LIVES + 1) {
+            settings.change(true);
+            lives.push(settings.starting_lives);
+        }
+        assert!(lives.iter().all(|l| (MIN_STARTING_LIVES..=MAX_STARTING_LIVES).contains(l)));
+        assert_eq!(lives.last(), Some(&(PLAYER_START_LIVES as u32)));
+        settings.starting_lives = MIN_STARTING_LIVES;
+        settings.change(false);
+        assert_eq!(settings.starting_lives, MAX_STARTING_LIVES);
+
+        select(&mut settings, SettingsItem::BonusLife);
+        for _ in 0..BONUS_LIFE_SCORES.len() * 2 {
+            settings.change(false);
+            assert!(BONUS_LIFE_SCORES.contains(&settings.bonus_life_score));
+        }
+
+        select(&mut settings, SettingsItem::MasterVolume);
+        for _ in 0..100 {
+            settings.change(true);
+        }
+        assert_eq!(settings.master_volume, 1.0);
+    }
+
+    #[test]
+    fn store_rejects_invalid_settings() {
+        let mut config = Config::default();
+        let mut settings = Settings::new(&config);
+        settings.starting_lives = MAX_STARTING_LIVES + 1;
+        settings.free_play = !config.get_free_play();
+        assert!(!settings.store(&mut config));
+        // nothing is stored
+        assert_eq!(config.get_starting_lives(), PLAYER_START_LIVES as u32);
+        assert_eq!(config.get_free_play(), Config::default().get_free_play());
+
+        settings.starting_lives = MAX_STARTING_LIVES;
+        settings.bonus_life_score = 1000;
+        assert!(settings.store(&mut config));
+        assert_eq!(config.get_starting_lives(), MAX_STARTING_LIVES);
+        assert_eq!(config.get_bonus_life_score(), 1000);
+        assert_eq!(config.get_free_play(), settings.free_play);
+    }
+}
@@ -1,16 +1,35 @@
 //! Description: 
 //! 
-//! Handle sound playback. All very simply sound effects and the music for the inpending 
+//! Handle sound playback. All very simply sound effects and the march of the inpending 
 //! invasion.
 //! 
+//! The player's shot, alien explosions, and the UFO are panned across the stereo mix to follow
+//! where they happen on screen, unless mono sound is chosen.
+//! 
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 
 extern crate rodio;
 
 use std::sync::Arc;
-use std::io::Read;
 use std::io;
-use rodio::{Device, Sink, Source};
+use std::path::Path;
+use std::time::Duration;
+use rodio::{Sink, Source};
+use rodio::source::SineWave;
+
+use crate::audio_backend::*;
+use crate::synth::*;
+use crate::mixer::*;
+use crate::sound_pack::*;
+use crate::asset::*;
+use crate::sound_check::decoder;
+
+/// notes (frequency in Hz, duration in ms) of the jingle played when an extra life is awarded
+const EXTRA_LIFE_JINGLE: [(u32, u64); 4] = [(523, 80), (659, 80), (784, 80), (1047, 200)];
+/// amplitude of the extra life jingle
+const EXTRA_LIFE_AMPLITUDE: f32 = 0.3;
+/// gain applied to sampled effects, which are much louder than the march
+const SAMPLE_EFFECTS_GAIN: f32 = 0.3;
 
 /// Static sound data stored in memory.
 /// It is `Arc`'ed, so cheap to clone.
@@ -18,11 +37,9 @@ use rodio::{Device, Sink, Source};
 pub struct SoundData(Arc<[u8]>);
 
 impl SoundData {
-    /// Load the file at the given path and create a new `SoundData` from it.
-    pub fn new(str: &str) -> Self {
-        let mut reader = std::fs::File::open(str).unwrap();
-
-        SoundData::from_read(&mut reader)
+    /// Load the asset at the given path, relative to the asset root, and create a new `SoundData` from it.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, AssetError> {
+        load_asset(path).map(|data| SoundData::from_bytes(&data))
     }
 
     /// Copies the data in the given slice into a new `SoundData` object.
@@ -30,22 +47,95 @@ impl SoundData {
         SoundData(Arc::from(data))
     }
 
-    /// Creates a `SoundData` from any `Read` object; this involves
-    /// copying it into a buffer.
-    pub fn from_read<R>(reader: &mut R) -> Self
-    where
-        R: Read,
-    {
-        let mut buffer = Vec::new();
-        let _ = reader.read_to_end(&mut buffer).unwrap();
+    /// Indicates if the data can be played as a sound.
+    pub fn can_play(&self) -> bool {
+        decoder(self.clone()).is_ok()
+    }
 
-        SoundData::from(buffer)
+    /// Decode the data into samples, which can then be played without decoding again.
+    pub fn decode(&self) -> Option<DecodedSoundData> {
+        let decoder = rodio::Decoder::new(io::Cursor::new(self.clone())).ok()?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+
+        Some(DecodedSoundData {
+            samples: decoder.collect::<Vec<i16>>().into(),
+            channels,
+            sample_rate,
+        })
     }
+}
 
-    /// Indicates if the data can be played as a sound.
-    pub fn can_play(&self) -> bool {
-        let cursor = io::Cursor::new(self.clone());
-        rodio::Decoder::new(cursor).is_ok()
+/// Sound data decoded to samples, stored in memory.
+/// It is `Arc`'ed, so cheap to clone.
+#[derive(Clone, Debug)]
+pub struct DecodedSoundData {
+    /// interleaved samples
+    samples: Arc<[i16]>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl DecodedSoundData {
+    /// Create a source playing the samples, from the start.
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `looped`: play the samples forever, rather than once
+    pub fn play(&self, looped: bool) -> DecodedSource {
+        DecodedSource {
+            data: self.clone(),
+            position: 0,
+            looped,
+        }
+    }
+}
+
+/// Source playing decoded sound data, which shares the samples rather than copying them.
+pub struct DecodedSource {
+    data: DecodedSoundData,
+    /// index of next sample
+    position: usize,
+    /// start again from the beginning once the end is reached
+    looped: bool,
+}
+
+impl Iterator for DecodedSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.position >= self.data.samples.len() {
+            if !self.looped || self.data.samples.is_empty() {
+                return None;
+            }
+            self.position = 0;
+        }
+
+        let sample = self.data.samples[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for DecodedSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.data.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.data.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        if self.looped {
+            return None;
+        }
+        let frames = self.data.samples.len() / self.data.channels.max(1) as usize;
+        Some(Duration::from_secs_f64(frames as f64 / self.data.sample_rate as f64))
     }
 }
 
@@ -76,9 +166,17 @@ impl AsRef<[u8]> for SoundData {
 }
 
 
+/// where sound effects come from
+enum Effects {
+    /// samples loaded from a sound pack
+    Samples(Box<SoundPack>),
+    /// synthesised in real time
+    Synth,
+}
+
 pub struct Sound {
-    /// output device for audio playback
-    output_device: Device,
+    /// backend providing sinks for audio playback
+    backend: Box<dyn AudioBackend>,
     // give effects their own sinks so they can happen concurrently
     /// sink for playing effect
     player_explosion_sink: Sink,
@@ -86,136 +184,379 @@ pub struct Sound {
     alien_explosion_sink: Sink,
     /// sink for player bullet
     player_shot_sink: Sink,
-    /// ufo sink
+    /// ufo sink, the ufo effect stays queued, and is paused when not on screen
     ufo_sink: Sink,
-    /// sink for playing sound track
-    music_sink: Vec<Sink>,
-    current_bpm: usize,
+    /// sink for ufo being hit, which replaces the ufo effect
+    ufo_hit_sink: Sink,
+    /// sink for extra life jingle
+    extra_life_sink: Sink,
+    /// sink for march notes
+    march_sink: Sink,
+    /// cuts short the march note playing, when the next starts
+    march_cut: CutControl,
+    /// bus levels
+    mixer: Mixer,
+    /// width of the stage, in world coordinates, used to pan effects
+    width: f32,
+    /// are effects played centred, rather than panned
+    mono: bool,
+    /// stereo position of the ufo effect, which moves while it plays
+    ufo_pan: PanControl,
 
     // sound effects
-    player_shot_data: io::Cursor<SoundData>,
-    player_explosion_data: io::Cursor<SoundData>,
-    alien_explosion_data: io::Cursor<SoundData>,
+    effects: Effects,
 }
 
 impl Sound {
-    pub fn new(
-        player_shot: &str,
-        player_explosion: &str,
-        alien_explosion: &str,
-        ufo_effect: &str,
-        music: Vec<&str>) -> Self{
-
-        let player_shot = SoundData::new(player_shot);
-        let player_explosion = SoundData::new(player_explosion);
-        let alien_explosion = SoundData::new(alien_explosion);
-    
-        let mut music_sinks = vec![];
-
-        let device = rodio::default_output_device().unwrap();
-        let player_explosion_sink = Sink::new(&device);
-        let player_shot_sink = Sink::new(&device);
-        
-        let alien_explosion_sink = Sink::new(&device);
-        
-        // for the ufo we simply play the same sound looped, while on screen
-        let ufo_effect = SoundData::new(ufo_effect);
-        let ufo_sink = Sink::new(&device);
-        let effect = rodio::Decoder::new(io::Cursor::new(ufo_effect)).unwrap()
-                .repeat_infinite().
-                speed(1.0);
-        ufo_sink.append(effect);
-        ufo_sink.pause();
-
-        for s in music {
-            let music = SoundData::new(s);
-            let music = rodio::Decoder::new(io::Cursor::new(music)).unwrap()
-                .repeat_infinite().
-                speed(1.0);
+    /// create sound with effects from a sound pack
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `backend`: backend providing sinks for audio playback
+    /// * `pack`: path to the sound pack's JSON manifest
+    /// * `width`: width of the stage, in world coordinates, used to pan effects
+    /// * `report`: report that sounds which cannot be played are added to, as warnings
+    pub fn new(backend: Box<dyn AudioBackend>, pack: &str, width: u32, report: &mut AssetReport) -> Self {
+        Sound::with_effects(backend, Effects::Samples(Box::new(SoundPack::load(pack, report))), width)
+    }
 
-            let music_sink   = Sink::new(&device);
-            music_sink.append(music);
-            music_sink.pause();
-            music_sinks.push(music_sink);
-        }
+    /// create sound with synthesised effects, no sound files are needed
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `backend`: backend providing sinks for audio playback
+    /// * `width`: width of the stage, in world coordinates, used to pan effects
+    pub fn new_synth(backend: Box<dyn AudioBackend>, width: u32) -> Self {
+        Sound::with_effects(backend, Effects::Synth, width)
+    }
 
-        // lower the sounds of effects, compared to the music
-        // should really do this in ableton
-        player_shot_sink.set_volume(0.1);
-        alien_explosion_sink.set_volume(0.1);
-        player_explosion_sink.set_volume(0.1);
-        ufo_sink.set_volume(0.1);
+    /// create sound, with sinks for each effect
+    fn with_effects(mut backend: Box<dyn AudioBackend>, effects: Effects, width: u32) -> Self {
+        let ufo_sink = backend.new_sink();
+        ufo_sink.pause();
+        let player_explosion_sink = backend.new_sink();
+        let player_shot_sink = backend.new_sink();
+        
+        let alien_explosion_sink = backend.new_sink();
+        let extra_life_sink = backend.new_sink();
+        let ufo_hit_sink = backend.new_sink();
+        let march_sink = backend.new_sink();
 
-        Sound {
-            // TODO: check devices and so on
-            output_device: device,
+        let sound = Sound {
+            backend,
             player_explosion_sink,
             alien_explosion_sink,
             player_shot_sink,
             ufo_sink,
-            music_sink: music_sinks,
-            current_bpm: 0,
-            player_shot_data: io::Cursor::new(player_shot),
-            player_explosion_data: io::Cursor::new(player_explosion),
-            alien_explosion_data: io::Cursor::new(alien_explosion),
+            ufo_hit_sink,
+            extra_life_sink,
+            march_sink,
+            march_cut: CutControl::default(),
+            mixer: Mixer::new(1.0, 1.0, 1.0),
+            width: width.max(1) as f32,
+            mono: false,
+            ufo_pan: PanControl::default(),
+            effects,
+        };
+        sound.apply_levels();
+        sound
+    }
+
+    /// switch to effects from a sound pack, unless it is already in use
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `pack`: path to the sound pack's JSON manifest
+    pub fn use_sound_pack(&mut self, pack: &str) {
+        if let Effects::Samples(current) = &self.effects {
+            if current.get_path() == pack {
+                return;
+            }
+        }
+        // the game is already running, so any sounds that cannot be played are only logged
+        let mut report = AssetReport::new();
+        self.set_effects(Effects::Samples(Box::new(SoundPack::load(pack, &mut report))));
+        report.log_warnings();
+    }
+
+    /// switch to synthesised effects, unless they are already in use
+    pub fn use_synth(&mut self) {
+        if let Effects::Synth = self.effects {
+            return;
+        }
+        self.set_effects(Effects::Synth);
+    }
+
+    /// replace the effects, the ufo effect is queued, so its sink is replaced too
+    fn set_effects(&mut self, effects: Effects) {
+        self.effects = effects;
+        // dropping a sink stops it
+        self.ufo_sink = self.backend.new_sink();
+        self.ufo_sink.pause();
+        self.apply_levels();
+    }
+
+    /// set the volume of each sink from the level of its bus
+    fn apply_levels(&self) {
+        // sampled effects are lowered, compared to the march, synthesised effects are already balanced
+        let gain = match self.effects {
+            Effects::Samples(_) => SAMPLE_EFFECTS_GAIN,
+            Effects::Synth => 1.0,
+        };
+        let effects = self.mixer.gain(Bus::Effects) * gain;
+        for sink in [
+                &self.player_explosion_sink, 
+                &self.alien_explosion_sink, 
+                &self.player_shot_sink, 
+                &self.ufo_sink, 
+                &self.ufo_hit_sink, 
+                &self.extra_life_sink].iter() {
+            sink.set_volume(effects);
         }
+        self.march_sink.set_volume(self.mixer.gain(Bus::Music));
     }
 
-    /// play alien marching music
+    /// set the level of each bus, each in the range [0,1]
+    /// 
+    /// # Arguments 
     /// 
-    /// If there is music currenlty playing, then it is paused, and the new
-    /// bpm is started
+    /// * `master`: level of all buses
+    /// * `music`: level of march
+    /// * `effects`: level of all other sound effects
+    pub fn set_levels(&mut self, master: f32, music: f32, effects: f32) {
+        self.mixer.set_levels(master, music, effects);
+        self.apply_levels();
+    }
+
+    /// toggle mute for all sound
+    pub fn toggle_mute(&mut self) {
+        self.mixer.toggle_mute();
+        self.apply_levels();
+    }
+
+    /// toggle mute for the march
+    pub fn toggle_music_mute(&mut self) {
+        self.mixer.toggle_music_mute();
+        self.apply_levels();
+    }
+
+    /// set if effects are played centred, rather than panned
     /// 
     /// # Arguments 
     /// 
-    /// * `index`: index of music to play
-    pub fn play_music(&mut self, bpm: usize) {
-        // force to be inbounds, avoiding any panics
-        if bpm < self.music_sink.len() {
-            self.music_sink[self.current_bpm].pause();
-            self.music_sink[bpm].play();
-            self.current_bpm = bpm;
+    /// * `mono`: play effects centred
+    pub fn set_mono(&mut self, mono: bool) {
+        self.mono = mono;
+        if mono {
+            self.ufo_pan.set(0.0);
         }
     }
 
-    /// returns the number of different music speed variants
-    pub fn number_of_music_variants(&self) -> usize {
-        self.music_sink.len()
+    /// returns the stereo position of an effect, from its position on the stage
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `x`: horizontal position of what made the effect
+    fn pan(&self, x: u32) -> PanControl {
+        if self.mono {
+            PanControl::default()
+        }
+        else {
+            PanControl::new(x as f32 / self.width * 2.0 - 1.0)
+        }
+    }
+
+    /// update audio backend, called once per game tick
+    pub fn update(&mut self) {
+        self.backend.update();
+    }
+
+    /// finish with audio backend, called once the game is exiting
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.backend.finish()
     }
 
-    /// pause current music if playing, if no music is playing nothing is changed
-    pub fn pause_music(&self) {
-        self.music_sink[self.current_bpm].pause();
+    /// play a note of the alien march, the march is always synthesised
+    /// 
+    /// If the previous note is still playing then it is cut short, rather than the new note waiting, so 
+    /// every step of the swarm starts its note, however fast the swarm moves
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `note`: index of note to play
+    pub fn play_march_note(&self, note: usize) {
+        self.march_cut.cut();
+        self.march_sink.append(Cut::new(Effect::new(march_note(note)), self.march_cut.clone()));
     }
 
     /// play sound for player's shot
-    pub fn play_player_shot(&self) {
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `x`: horizontal position of the player's cannon
+    pub fn play_player_shot(&self, x: u32) {
         if self.player_shot_sink.empty() {
-            let sound = rodio::Decoder::new(self.player_shot_data.clone()).unwrap();
-            self.player_shot_sink.append(sound);
+            let pan = self.pan(x);
+            match &self.effects {
+                Effects::Samples(pack) => play_sample(&self.player_shot_sink, &pack.shot_fired, pan),
+                Effects::Synth => self.player_shot_sink.append(Pan::new(Effect::new(SHOT), pan)),
+            }
         }
     }
 
     /// play sound for player's explosion
     pub fn play_player_explosion(&self) {
-        let sound = rodio::Decoder::new(self.player_explosion_data.clone()).unwrap();
-        self.player_explosion_sink.append(sound);
+        match &self.effects {
+            Effects::Samples(pack) => 
+                play_sample(&self.player_explosion_sink, &pack.player_killed, PanControl::default()),
+            Effects::Synth => self.player_explosion_sink.append(Effect::new(PLAYER_EXPLOSION)),
+        }
     }
 
-    /// play sound for player's explosion
-    pub fn play_alien_explosion(&self) {
-            let sound = rodio::Decoder::new(self.alien_explosion_data.clone()).unwrap();
-            self.alien_explosion_sink.append(sound);
-            
+    /// play sound for alien's explosion
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `x`: horizontal position of the alien
+    pub fn play_alien_explosion(&self, x: u32) {
+        let pan = self.pan(x);
+        match &self.effects {
+            Effects::Samples(pack) => play_sample(&self.alien_explosion_sink, &pack.alien_killed, pan),
+            Effects::Synth => self.alien_explosion_sink.append(Pan::new(Effect::new(ALIEN_EXPLOSION), pan)),
+        }
     }
+    /// play sound for extra life, if the sound pack does not have one then a jingle is generated
+    pub fn play_extra_life(&self) {
+        if let Effects::Samples(pack) = &self.effects {
+            if pack.extra_life.is_some() {
+                play_sample(&self.extra_life_sink, &pack.extra_life, PanControl::default());
+                return;
+            }
+        }
 
-    /// play sound for player's explosion
-    pub fn play_ufo(&self) {
+        for (frequency, duration) in EXTRA_LIFE_JINGLE.iter() {
+            let note = SineWave::new(*frequency)
+                .take_duration(Duration::from_millis(*duration))
+                .amplify(EXTRA_LIFE_AMPLITUDE);
+            self.extra_life_sink.append(note);
+        }
+    }
+
+    /// play sound for ufo, queuing the effect if it is not already
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `x`: horizontal position of the ufo
+    pub fn play_ufo(&self, x: u32) {
+        self.move_ufo(x);
+        if self.ufo_sink.empty() {
+            match &self.effects {
+                Effects::Samples(pack) => play_sample(&self.ufo_sink, &pack.ufo_spawned, self.ufo_pan.clone()),
+                Effects::Synth => self.ufo_sink.append(Pan::new(Effect::new(UFO), self.ufo_pan.clone())),
+            }
+        }
         self.ufo_sink.play();    
     }
 
+    /// move the ufo effect, across the stereo mix, to follow the ufo
+    /// 
+    /// # Arguments 
+    /// 
+    /// * `x`: horizontal position of the ufo
+    pub fn move_ufo(&self, x: u32) {
+        self.ufo_pan.set(self.pan(x).get());
+    }
+
     /// pause ufo effect if playing, if not playing nothing is changed
     pub fn pause_ufo(&self) {
         self.ufo_sink.pause();
     }
-}
\ No newline at end of file
+
+    /// play sound for ufo being hit, in place of the ufo effect, where the ufo was
+    pub fn play_ufo_hit(&self) {
+        self.ufo_sink.pause();
+        match &self.effects {
+            Effects::Samples(pack) => play_sample(&self.ufo_hit_sink, &pack.ufo_killed, self.ufo_pan.clone()),
+            Effects::Synth => self.ufo_hit_sink.append(Pan::new(Effect::new(UFO_HIT), self.ufo_pan.clone())),
+        }
+    }
+}
+
+/// play a sound from a pack, a sound that is missing from the pack is silent
+/// 
+/// # Arguments 
+/// 
+/// * `sink`: sink to play sound on
+/// * `sound`: sound to play
+/// * `pan`: stereo position of sound
+fn play_sample(sink: &Sink, sound: &Option<PackSound>, pan: PanControl) {
+    if let Some(sound) = sound {
+        sink.append(Pan::new(sound.source().convert_samples::<f32>(), pan));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+    use crate::audio_backend::tests::SharedWav;
+
+    #[test]
+    fn fast_march_starts_a_note_each_step() {
+        // the swarm steps every other tick, much quicker than a note lasts
+        const STEPS: usize = 6;
+        const TICKS_PER_STEP: usize = 2;
+
+        let wav = SharedWav::default();
+        let mut sound = Sound::new_synth(Box::new(CaptureBackend::from_writer(wav.clone()).unwrap()), 100);
+        for step in 0..STEPS {
+            sound.play_march_note(step);
+            for _ in 0..TICKS_PER_STEP {
+                sound.update();
+            }
+        }
+        sound.finish().unwrap();
+
+        // notes fade away, so each step's note starts louder than the previous note has faded to, the first
+        // note can start a little late, as the sink starts idle, so is only compared by how far it fades
+        let samples = wav.samples();
+        let tick = FRAMES_PER_TICK * MIX_CHANNELS as usize;
+        let level = |samples: &[i16]| samples.iter().map(|s| (*s as i32).abs()).max().unwrap();
+        let peak = level(&samples);
+        for step in 1..STEPS {
+            let start = step * TICKS_PER_STEP * tick;
+            assert!(level(&samples[start - 200..start]) < peak * 8 / 10, "step {} faded too little", step - 1);
+            assert!(level(&samples[start..start + 200]) >= peak * 9 / 10, "step {} did not start a note", step);
+        }
+    }
+
+    #[test]
+    fn effects_pan_to_the_screen_edges_unless_mono() {
+        const WIDTH: u32 = 200;
+        let mut sound = Sound::new_synth(Box::new(CaptureBackend::from_writer(SharedWav::default()).unwrap()), WIDTH);
+
+        assert_eq!(sound.pan(0).get(), -1.0);
+        assert_eq!(sound.pan(WIDTH / 2).get(), 0.0);
+        assert_eq!(sound.pan(WIDTH).get(), 1.0);
+        // e.g. the ufo just off the screen
+        assert_eq!(sound.pan(WIDTH + 50).get(), 1.0);
+
+        // at the edges the other channel is silent, (left, right) for each frame
+        let frames = |pan: PanControl| {
+            let samples: Vec<f32> = Pan::new(SamplesBuffer::new(1, MIX_SAMPLE_RATE, vec![0.5f32; 4]), pan).collect();
+            samples.chunks(2).map(|frame| (frame[0], frame[1])).collect::<Vec<_>>()
+        };
+        assert!(frames(sound.pan(0)).iter().all(|frame| *frame == (0.5, 0.0)));
+        assert!(frames(sound.pan(WIDTH)).iter().all(|frame| *frame == (0.0, 0.5)));
+        assert!(frames(sound.pan(WIDTH / 2)).iter().all(|frame| *frame == (0.5, 0.5)));
+
+        // mono plays everything centred, including a ufo already panned
+        sound.ufo_pan.set(1.0);
+        sound.set_mono(true);
+        assert_eq!(sound.ufo_pan.get(), 0.0);
+        for x in [0, WIDTH / 2, WIDTH] {
+            assert_eq!(sound.pan(x).get(), 0.0);
+            assert!(frames(sound.pan(x)).iter().all(|frame| *frame == (0.5, 0.5)));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Description:
+//!
+//! Checks that a sound file can be decoded, before handing it to rodio, which can panic or crash on
+//! data it does not support. Kept apart from the sounds themselves, so that the asset tools check
+//! sounds exactly as the game does.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::io;
+
+/// returns a decoder for a sound, or why it cannot be decoded
+///
+/// # Arguments
+///
+/// * `data` - Sound file's contents
+pub fn decoder<D>(data: D) -> Result<rodio::Decoder<io::Cursor<D>>, String>
+    where D: AsRef<[u8]> + Send + 'static {
+    // rodio tries each decoder in turn, and its MP3 decoder can crash on data that is not audio, so
+    // only pass it data that starts like one of the formats it decodes
+    if !is_audio(data.as_ref()) {
+        return Err("not a WAV, Ogg, FLAC, or MP3 file".to_string());
+    }
+
+    // rodio accepts any WAV, but panics when decoding samples that are not 16 or 24 bit integers, or 
+    // 32 bit floats, e.g. 8 bit. a WAV it cannot read is passed on to its other decoders, so is rejected here
+    if is_wav(data.as_ref()) {
+        let reader = hound::WavReader::new(io::Cursor::new(data.as_ref())).map_err(|e| e.to_string())?;
+        let spec = reader.spec();
+        match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Int, 16) | (hound::SampleFormat::Int, 24) | (hound::SampleFormat::Float, 32) => (),
+            (format, bits) => return Err(format!("{} bit {:?} samples are not supported", bits, format)),
+        }
+    }
+
+    rodio::Decoder::new(io::Cursor::new(data)).map_err(|e| e.to_string())
+}
+
+/// returns true if data starts with the header of a WAV, Ogg, FLAC, or MP3 file
+///
+/// # Arguments
+///
+/// * `data` - Sound file's contents
+fn is_audio(data: &[u8]) -> bool {
+    is_wav(data) ||
+    data.starts_with(b"OggS") ||
+    data.starts_with(b"fLaC") ||
+    data.starts_with(b"ID3") ||
+    (data.len() > 1 && data[0] == 0xff && data[1] & 0xe0 == 0xe0)
+}
+
+/// returns true if data starts with the header of a WAV file
+///
+/// # Arguments
+///
+/// * `data` - Sound file's contents
+fn is_wav(data: &[u8]) -> bool {
+    data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE")
+}
@@ -0,0 +1,187 @@
+//! Description:
+//!
+//! Sound packs, a JSON manifest mapping the names of game events to the sound files played for
+//! them, each with its own gain and whether it loops, e.g.
+//!
+//! ```json
+//! {
+//!     "name": "arcade",
+//!     "sounds": {
+//!         "shot_fired": { "file": "sounds/player_shoot_16bit.wav", "gain": 1.0 },
+//!         "ufo_spawned": { "file": "sounds/ufo.wav", "loop": true }
+//!     }
+//! }
+//! ```
+//!
+//! Files are relative to the manifest. A missing, or undecodable, file is reported as a warning
+//! and its event is silent, rather than stopping the game.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::path::Path;
+
+use rodio::Source;
+
+use crate::sound::*;
+use crate::asset::*;
+pub use crate::sound_pack_json::*;
+
+impl SoundPackJSON {
+    pub fn from_json<P>(jsonfile: P) -> Result<Self, AssetError>
+        where P: AsRef<Path> {
+            read_json(jsonfile)
+    }
+}
+
+//------------------------------------------------------------------------------
+// Loaded sound pack
+//------------------------------------------------------------------------------
+
+/// a sound from a pack, loaded into memory and decoded once, so that playing it is cheap
+#[derive(Debug, Clone)]
+pub struct PackSound {
+    data: DecodedSoundData,
+    gain: f32,
+    looped: bool,
+}
+
+impl PackSound {
+    /// returns a new source playing the sound, at its gain, looped if required
+    pub fn source(&self) -> impl Source<Item = i16> + Send {
+        self.data.play(self.looped).amplify(self.gain)
+    }
+}
+
+/// sounds for each event, an event without a sound is silent
+#[derive(Debug, Clone, Default)]
+pub struct SoundPack {
+    /// file pack was loaded from
+    path: String,
+    pub shot_fired: Option<PackSound>,
+    pub player_killed: Option<PackSound>,
+    pub alien_killed: Option<PackSound>,
+    pub ufo_spawned: Option<PackSound>,
+    pub ufo_killed: Option<PackSound>,
+    /// if not provided the extra life jingle is played
+    pub extra_life: Option<PackSound>,
+}
+
+impl SoundPack {
+    /// load a pack from its manifest, if the manifest cannot be read then every event is silent
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to pack's JSON manifest
+    /// * `report` - Report that sounds which cannot be played are added to, as warnings
+    pub fn load(path: &str, report: &mut AssetReport) -> Self {
+        let mut pack = SoundPack {
+            path: path.to_string(),
+            ..Default::default()
+        };
+
+        let json = match SoundPackJSON::from_json(path) {
+            Ok(json) => json,
+            Err(e) => {
+                report.warn(e);
+                return pack;
+            },
+        };
+        log::info!("loading sound pack \"{}\" from {}", json.name, path);
+
+        // files are relative to the manifest
+        let root = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+        for (event, sound) in json.sounds.iter() {
+            let slot = match event.as_str() {
+                "shot_fired" => &mut pack.shot_fired,
+                "player_killed" => &mut pack.player_killed,
+                "alien_killed" => &mut pack.alien_killed,
+                "ufo_spawned" => &mut pack.ufo_spawned,
+                "ufo_killed" => &mut pack.ufo_killed,
+                "extra_life" => &mut pack.extra_life,
+                _ => {
+                    log::warn!("unknown event \"{}\" in sound pack {}, ignored", event, path);
+                    continue;
+                },
+            };
+            *slot = load_sound(&root.join(&sound.file), sound, report);
+        }
+
+        for event in SOUND_PACK_EVENTS.iter() {
+            if !json.sounds.contains_key(*event) {
+                log::warn!("no sound for event \"{}\" in sound pack {}", event, path);
+            }
+        }
+
+        pack
+    }
+
+    /// returns the file pack was loaded from
+    #[inline]
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// load a pack's sound, returning None, and adding why to the report, if it cannot be played
+///
+/// # Arguments
+///
+/// * `file` - Path to sound file
+/// * `sound` - Sound's description from the manifest
+/// * `report` - Report the sound is added to, as a warning, if it cannot be played
+fn load_sound(file: &Path, sound: &SoundJSON, report: &mut AssetReport) -> Option<PackSound> {
+    let data = match SoundData::new(file) {
+        Ok(data) => data,
+        Err(e) => {
+            report.warn(e);
+            return None;
+        },
+    };
+
+    let decoded = if data.can_play() { data.decode() } else { None };
+    if decoded.is_none() {
+        report.warn(AssetError::UndecodableSound { path: asset_path(file).display().to_string() });
+    }
+
+    decoded.map(|data| PackSound {
+        data,
+        gain: sound.gain.max(0.0),
+        looped: sound.looped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unplayable_sounds_are_silent_with_a_warning() {
+        let dir = std::env::temp_dir().join(format!("space-invaders-sound-pack-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.wav"), b"RIFF not really a wav file").unwrap();
+        let shoot = std::fs::canonicalize("assets/sounds/player_shoot_16bit.wav").unwrap();
+        let manifest = dir.join("pack.json");
+        std::fs::write(&manifest, format!(r#"{{
+            "name": "test",
+            "sounds": {{
+                "shot_fired": {{ "file": {:?} }},
+                "player_killed": {{ "file": "missing.wav" }},
+                "alien_killed": {{ "file": "broken.wav" }}
+            }}
+        }}"#, shoot)).unwrap();
+
+        let mut report = AssetReport::new();
+        let pack = SoundPack::load(manifest.to_str().unwrap(), &mut report);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(pack.shot_fired.is_some());
+        assert!(pack.player_killed.is_none());
+        assert!(pack.alien_killed.is_none());
+        // the game still starts
+        assert!(report.is_empty());
+        let warnings = report.to_string();
+        assert!(warnings.contains("2 warning(s)"), "{}", warnings);
+        assert!(warnings.contains("missing.wav"), "{}", warnings);
+        assert!(warnings.contains("broken.wav"), "{}", warnings);
+    }
+}
@@ -0,0 +1,40 @@
+//! Description:
+//!
+//! JSON representation of sound packs, kept apart from the sound pack itself, so that the asset
+//! tools can share it.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::collections::HashMap;
+
+/// event names a pack is expected to provide a sound for, an extra life sound is optional
+pub const SOUND_PACK_EVENTS: [&str; 5] = [
+    "shot_fired",
+    "player_killed",
+    "alien_killed",
+    "ufo_spawned",
+    "ufo_killed",
+];
+
+//------------------------------------------------------------------------------
+// Sound pack JSON representation
+//------------------------------------------------------------------------------
+
+fn default_gain() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundJSON {
+    pub file: String,
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    #[serde(default, rename = "loop")]
+    pub looped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundPackJSON {
+    pub name: String,
+    pub sounds: HashMap<String, SoundJSON>,
+}
@@ -4,64 +4,25 @@
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 
 use std::path::Path;
-use std::fs::File;
+use std::sync::Arc;
 use std::collections::HashMap;
 
-use image::{DynamicImage, Rgba, ImageBuffer};
+use image::{DynamicImage, ImageBuffer};
 
 use serde::{Deserialize, Serialize};
 
 use crate::frame::*;
+use crate::asset::*;
+pub use crate::sprite_sheet_json::*;
 
 //------------------------------------------------------------------------------
 // Sprite sheet JSON representation
 //------------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct XYWHJSON {
-    pub x: i32,
-    pub y: i32,
-    pub w: i32,
-    pub h: i32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WHJSON {
-    pub w: i32,
-    pub h: i32,
-}
-
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SpriteJSON {
-    pub frame: XYWHJSON,
-    pub rotated: bool,
-    pub trimmed: bool,
-    pub spriteSourceSize: XYWHJSON,
-    pub sourceSize: WHJSON,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MetaJSON {
-    pub app: String,
-    pub version: String,
-    pub image: String,
-    pub format: String,
-    pub size: WHJSON,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SheetJSON {
-    pub frames: HashMap<String, SpriteJSON>,
-    pub meta: MetaJSON,
-}
-
 impl SheetJSON {
-    pub fn new<P>(jsonfile: P) -> Self
+    pub fn new<P>(jsonfile: P) -> Result<Self, AssetError>
         where P: AsRef<Path> {
-            let json = String::new();
-            let file = File::open(jsonfile).expect("Unable open sprite sheet JSON file");
-            serde_json::from_reader(file).expect("Invalid sprite sheet JSON file")
+            read_json(jsonfile)
     }
 }
 
@@ -76,139 +37,545 @@ impl SheetJSON {
 // Sprites
 //------------------------------------------------------------------------------
 
+/// how a sprite's pixels are combined with those already drawn, where S is the sprite's colour, D the
+/// colour already drawn, and s the sprite's alpha
+#[allow(dead_code)] // not every mode is used by the game
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlendMode {
-    Normal,             // O = sS
-    Add,                // O = sS + dD
-    Substract,          // O = sS - dD
-    RevSubstract,       // O = dD - sS
-    Min,                // O = min(Sr, Dr)
-    Max,                // O = max(Sr, Dr)
+    Normal,             // O = sS + (1-s)D
+    Add,                // O = sS + D
+    Substract,          // O = s(S - D) + (1-s)D
+    RevSubstract,       // O = D - sS
+    Min,                // O = s min(S, D) + (1-s)D
+    Max,                // O = s max(S, D) + (1-s)D
+}
+
+impl BlendMode {
+    /// returns a pixel blended with the pixel already drawn, weighted by the pixel's alpha
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - Pixel being drawn
+    /// * `dst` - Pixel already drawn
+    pub fn blend(self, src: &[u8; 4], dst: &[u8; 4]) -> [u8; 4] {
+        let sa = src[3] as u32;
+        let mix = |o: u32, d: u32| (o * sa + d * (255 - sa)) / 255;
+        let channel = |s: u8, d: u8| {
+            let (s, d) = (s as u32, d as u32);
+            let o = match self {
+                BlendMode::Normal => mix(s, d),
+                BlendMode::Add => (d + s * sa / 255).min(255),
+                BlendMode::Substract => mix(s.saturating_sub(d), d),
+                BlendMode::RevSubstract => d.saturating_sub(s * sa / 255),
+                BlendMode::Min => mix(s.min(d), d),
+                BlendMode::Max => mix(s.max(d), d),
+            };
+            o as u8
+        };
+
+        [
+            channel(src[0], dst[0]),
+            channel(src[1], dst[1]),
+            channel(src[2], dst[2]),
+            (sa + dst[3] as u32 * (255 - sa) / 255) as u8,
+        ]
+    }
+}
+
+/// how a sprite is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawStyle {
+    /// how pixels are combined with those already drawn
+    pub blend: BlendMode,
+    /// each pixel's colour, and alpha, is multiplied by the tint
+    pub tint: [u8; 4],
+    /// from 0, invisible, to 1, as drawn
+    pub opacity: f32,
+}
+
+impl DrawStyle {
+    /// drawn as is, blending only pixels that are partly transparent
+    pub const NORMAL: DrawStyle = DrawStyle { blend: BlendMode::Normal, tint: [0xFF; 4], opacity: 1.0 };
+
+    /// create a style
+    ///
+    /// # Arguments
+    ///
+    /// * `blend` - How pixels are combined with those already drawn
+    /// * `tint` - Colour pixels are multiplied by
+    /// * `opacity` - From 0, invisible, to 1, as drawn
+    pub fn new(blend: BlendMode, tint: [u8; 4], opacity: f32) -> Self {
+        DrawStyle {
+            blend,
+            tint,
+            opacity: opacity.clamp(0.0, 1.0),
+        }
+    }
+
+    /// draw a pixel to the frame in this style
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x position in frame, in bytes, i.e. 4 per pixel
+    /// * `y` - y position in frame
+    /// * `rgba` - Pixel to draw
+    /// * `frame` - Frame to draw to
+    #[inline]
+    pub fn put_pixel<'a>(&self, x: u32, y: u32, rgba: &[u8; 4], frame: &mut Frame<'a>) {
+        if let Some(dst) = frame.pixel_mut(x, y) {
+            self.draw(*rgba, dst);
+        }
+    }
+
+    /// draw a pixel over one already drawn, in this style
+    ///
+    /// # Arguments
+    ///
+    /// * `rgba` - Pixel to draw
+    /// * `dst` - Pixel already drawn, 4 bytes
+    #[inline]
+    pub fn draw(&self, rgba: [u8; 4], dst: &mut [u8]) {
+        // most pixels are opaque and drawn as is, so need no blending
+        if *self == DrawStyle::NORMAL && rgba[3] == 0xFF {
+            dst.copy_from_slice(&rgba);
+            return;
+        }
+
+        let tint = |c: u8, t: u8| ((c as u32 * t as u32) / 255) as u8;
+        let src = [
+            tint(rgba[0], self.tint[0]),
+            tint(rgba[1], self.tint[1]),
+            tint(rgba[2], self.tint[2]),
+            (tint(rgba[3], self.tint[3]) as f32 * self.opacity) as u8,
+        ];
+        if src[3] == 0 {
+            return;
+        }
+        let blended = self.blend.blend(&src, &[dst[0], dst[1], dst[2], dst[3]]);
+        dst.copy_from_slice(&blended);
+    }
+}
+
+impl Default for DrawStyle {
+    fn default() -> Self {
+        DrawStyle::NORMAL
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Sprite {
-    /// x postion of sprite within sheet
+    /// x postion of sprite's frame within sheet
     pub x: u32,
-    /// y position of sprite within sheet
+    /// y position of sprite's frame within sheet
     pub y: u32,
-    /// width of sprite
+    /// width of sprite, before it was trimmed
     pub width: u32,
-    /// height of sprite
+    /// height of sprite, before it was trimmed
     pub height: u32,
+    /// x position of trimmed frame within sprite
+    pub offset_x: u32,
+    /// y position of trimmed frame within sprite
+    pub offset_y: u32,
+    /// width of trimmed frame, before it was rotated
+    pub trimmed_width: u32,
+    /// height of trimmed frame, before it was rotated
+    pub trimmed_height: u32,
+    /// frame is rotated 90° clockwise within the sheet
+    pub rotated: bool,
+    /// sprite's pixels, decoded from the sheet when loaded, shared between clones
+    spans: Arc<SpriteSpans>,
+    /// sprite's mask, used for pixel perfect collisions, built when loaded and shared between clones
+    mask: Arc<SpriteMask>,
+}
+
+/// a run of pixels, along a row of a sprite, none of which are transparent
+#[derive(Debug, Clone)]
+struct Span {
+    /// x position of span's first pixel within sprite
+    x: u32,
+    /// y position of span within sprite
+    y: u32,
+    /// offset of span's first pixel within the sprite's pixels, in bytes
+    start: usize,
+    /// number of pixels in span
+    len: u32,
+    /// every pixel in span is opaque, so it can be copied without blending
+    opaque: bool,
+}
+
+/// a sprite's pixels, as runs of pixels that are not transparent, so that drawing skips transparent
+/// pixels and copies opaque ones a run at a time
+#[derive(Debug, Clone, Default)]
+struct SpriteSpans {
+    spans: Vec<Span>,
+    /// RGBA pixels for every span, one after the other
+    pixels: Vec<u8>,
+}
+
+/// a bit for each pixel of a sprite, set where it is not transparent, packed 64 pixels to a word
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpriteMask {
+    width: usize,
+    height: usize,
+    /// words in each row
+    stride: usize,
+    bits: Vec<u64>,
 }
 
-pub type SpriteMask = Vec<Vec<u8>>;
+impl SpriteMask {
+    /// create a mask with every pixel clear
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of mask, in pixels
+    /// * `height` - Height of mask, in pixels
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        SpriteMask {
+            width,
+            height,
+            stride,
+            bits: vec![0; stride * height],
+        }
+    }
+
+    /// returns width of mask, in pixels
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// returns height of mask, in pixels
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// returns true if pixel is set, anything outside of the mask is clear
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.bits[y * self.stride + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    /// set or clear a pixel, anything outside of the mask is ignored
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        if x < self.width && y < self.height {
+            let word = &mut self.bits[y * self.stride + x / 64];
+            if value {
+                *word |= 1 << (x % 64);
+            }
+            else {
+                *word &= !(1 << (x % 64));
+            }
+        }
+    }
+
+    /// returns 64 pixels of a row, starting at x, pixels outside of the mask are clear
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - Row
+    /// * `x` - x position of first pixel, which may be negative
+    #[inline]
+    fn row_bits(&self, y: usize, x: i32) -> u64 {
+        if y >= self.height || x <= -64 || x >= self.width as i32 {
+            return 0;
+        }
+        let row = &self.bits[y * self.stride..(y + 1) * self.stride];
+        let word = |i: i32| if i >= 0 && (i as usize) < self.stride { row[i as usize] } else { 0 };
+        let (index, shift) = (x.div_euclid(64), x.rem_euclid(64));
+        if shift == 0 {
+            word(index)
+        }
+        else {
+            (word(index) >> shift) | (word(index + 1) << (64 - shift))
+        }
+    }
+
+    /// returns the range of words, within a row, that another mask placed at x overlaps, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Other mask
+    /// * `x` - x position of other mask, relative to this one
+    fn overlapped_words(&self, other: &SpriteMask, x: i32) -> std::ops::Range<usize> {
+        let start = x.max(0) as usize;
+        let end = (x + other.width as i32).clamp(0, self.width as i32) as usize;
+        if start >= end {
+            0..0
+        }
+        else {
+            start / 64..(end - 1) / 64 + 1
+        }
+    }
+
+    /// returns the range of rows that another mask placed at y overlaps
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Other mask
+    /// * `y` - y position of other mask, relative to this one
+    fn overlapped_rows(&self, other: &SpriteMask, y: i32) -> std::ops::Range<usize> {
+        let start = y.clamp(0, self.height as i32) as usize;
+        let end = (y + other.height as i32).clamp(0, self.height as i32) as usize;
+        start..end.max(start)
+    }
+
+    /// returns true if any pixel set in another mask, placed at a position relative to this one, is also set
+    /// in this mask
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Other mask
+    /// * `x` - x position of other mask, relative to this one, in pixels
+    /// * `y` - y position of other mask, relative to this one
+    pub fn overlaps(&self, other: &SpriteMask, x: i32, y: i32) -> bool {
+        let words = self.overlapped_words(other, x);
+        for row in self.overlapped_rows(other, y) {
+            let other_row = (row as i32 - y) as usize;
+            for word in words.clone() {
+                let bits = other.row_bits(other_row, (word * 64) as i32 - x);
+                if self.bits[row * self.stride + word] & bits != 0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// clear every pixel of this mask that is set in another mask, placed at a position relative to this one, 
+    /// i.e. stamp a hole the shape of the other mask
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Mask to stamp
+    /// * `x` - x position of other mask, relative to this one, in pixels
+    /// * `y` - y position of other mask, relative to this one
+    pub fn clear_masked(&mut self, other: &SpriteMask, x: i32, y: i32) {
+        let words = self.overlapped_words(other, x);
+        for row in self.overlapped_rows(other, y) {
+            let other_row = (row as i32 - y) as usize;
+            for word in words.clone() {
+                let bits = other.row_bits(other_row, (word * 64) as i32 - x);
+                self.bits[row * self.stride + word] &= !bits;
+            }
+        }
+    }
+
+    /// returns the x position of the first pixel set in part of a row, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - Row
+    /// * `xs` - Pixels of row to search
+    pub fn first_in_row(&self, y: usize, xs: std::ops::Range<usize>) -> Option<usize> {
+        let end = xs.end.min(self.width());
+        let mut x = xs.start;
+        while x < end {
+            // pixels from x to the end of the range, a word at a time
+            let mut bits = self.row_bits(y, x as i32);
+            if end - x < 64 {
+                bits &= (1 << (end - x)) - 1;
+            }
+            if bits != 0 {
+                return Some(x + bits.trailing_zeros() as usize);
+            }
+            x += 64;
+        }
+        None
+    }
+}
 
+/// print a mask, one row per line, for debugging
 pub fn print_sprite_mask(mask: &SpriteMask) {
-    for row in mask {
-        for col in row {
-            print!("{}", col);
+    for y in 0..mask.height() {
+        for x in 0..mask.width() {
+            print!("{}", mask.get(x, y) as u8);
         }  
         println!();
     }
 }
 
 impl Sprite {
-    /// create sprite
-    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
-        Sprite {
-            x,
-            y,
+    /// create sprite from its frame in the sprite sheet, which may be trimmed or rotated, decoding its pixels 
+    /// from the sheet
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Sprite's frame from the sprite sheet's JSON
+    /// * `sheet` - Sprite sheet
+    pub fn from_json(s: &SpriteJSON, sheet: &SpriteSheet) -> Self {
+        let (width, height, offset_x, offset_y) = if s.trimmed {
+            (s.sourceSize.w as u32, s.sourceSize.h as u32, s.spriteSourceSize.x as u32, s.spriteSourceSize.y as u32)
+        }
+        else {
+            (s.frame.w as u32, s.frame.h as u32, 0, 0)
+        };
+
+        let mut sprite = Sprite {
+            x: s.frame.x as u32,
+            y: s.frame.y as u32,
             width,
             height,
+            offset_x,
+            offset_y,
+            trimmed_width: s.frame.w as u32,
+            trimmed_height: s.frame.h as u32,
+            rotated: s.rotated,
+            spans: Arc::default(),
+            mask: Arc::default(),
+        };
+        sprite.spans = Arc::new(sprite.decode(sheet));
+        sprite.mask = Arc::new(sprite.create_mask());
+        sprite
+    }
+
+    /// returns the sheet's pixel at a position within the sprite, or None if it was trimmed away
+    ///
+    /// Sprites are drawn one pixel wider and taller than their frame, which with an extruded sheet repeats the
+    /// last column and row, so positions past the sprite's edge are clamped to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x position within sprite
+    /// * `sy` - y position within sprite
+    /// * `sheet` - Sprite sheet
+    #[inline]
+    fn pixel<'s>(&self, sx: u32, sy: u32, sheet: &'s SpriteSheet) -> Option<&'s [u8; 4]> {
+        let sx = sx.min(self.width.saturating_sub(1));
+        let sy = sy.min(self.height.saturating_sub(1));
+        if sx < self.offset_x || sy < self.offset_y {
+            return None;
+        }
+
+        let (tx, ty) = (sx - self.offset_x, sy - self.offset_y);
+        if tx >= self.trimmed_width || ty >= self.trimmed_height {
+            return None;
+        }
+
+        // rotating clockwise moves the frame's left column to the top row, read from right to left
+        let (px, py) = if self.rotated {
+            (self.x + self.trimmed_height - 1 - ty, self.y + tx)
+        }
+        else {
+            (self.x + tx, self.y + ty)
+        };
+
+        // anything outside of the sheet is transparent
+        if px < sheet.width && py < sheet.height {
+            Some(&sheet.texture.get_pixel(px, py).0)
+        }
+        else {
+            None
         }
     }
 
-    /// create a mask of the sprite
-    pub fn create_mask(&self, sheet: &SpriteSheet) -> SpriteMask {
-        let mut mask = vec![];
+    /// returns the sprite's pixels decoded from the sheet, as runs of pixels that are not transparent
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - Sprite sheet
+    fn decode(&self, sheet: &SpriteSheet) -> SpriteSpans {
+        let mut decoded = SpriteSpans::default();
         for sy in 0..=self.height {
-            let mut row = vec![];
-            for sx in 0..=self.width {
-                let Rgba(rgba) = sheet.texture.get_pixel(sx+self.x,sy+self.y);
-                if rgba[3] != 0 { 
-                    // push 1 if alpha channel not 0
-                    //print!("1");
-                    row.push(1);
+            let mut sx = 0;
+            while sx <= self.width {
+                // skip transparent pixels
+                if self.pixel(sx, sy, sheet).is_none_or(|rgba| rgba[3] == 0) {
+                    sx += 1;
+                    continue;
                 }
-                else {
-                    // print!("0");
-                    // push 0 is alpha channel 0
-                    row.push(0);
+
+                let (x, start) = (sx, decoded.pixels.len());
+                let mut opaque = true;
+                while let Some(rgba) = self.pixel(sx, sy, sheet).filter(|rgba| rgba[3] != 0 && sx <= self.width) {
+                    opaque &= rgba[3] == 0xFF;
+                    decoded.pixels.extend_from_slice(rgba);
+                    sx += 1;
                 }
+                decoded.spans.push(Span { x, y: sy, start, len: sx - x, opaque });
             }
-            mask.push(row);
-            // println!("");
         }
-
-        // println!("-----------");
-        // print_sprite_mask(&mask);
-
-        mask
+        decoded
     }
 
-    /// render sprite to frame with mask
-    pub fn render_with_mask<'a>(&self, x: u32, y: u32, mask: &SpriteMask, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        let mut px = x;
-        let mut py = y;
+    /// returns the sprite's mask, built when it was loaded
+    #[inline]
+    pub fn get_mask(&self) -> &SpriteMask {
+        &self.mask
+    }
 
-        for sy in 0..=self.height {
-            for sx in 0..=self.width {
-                let Rgba(rgba) = sheet.texture.get_pixel(sx+self.x,sy+self.y);
-                
-                // check mask not 0 
-                // NOTE: no need to check alpha channel, as mask would be zero in that case
-                if mask[sy as usize][sx as usize] != 0 { 
-                    frame.put_pixel(px, py, rgba);
-                    // clip if necessary
-                    // if  px < frame.width*4 && py < frame.height*4 {
-                    //     frame.frame[(px+py*frame.width*4) as usize]   = rgba[0];
-                    //     frame.frame[(px+1+py*frame.width*4) as usize] = rgba[1];
-                    //     frame.frame[(px+2+py*frame.width*4) as usize] = rgba[2];
-                    //     frame.frame[(px+3+py*frame.width*4) as usize] = rgba[3];
-                    // } 
-                    //}
-                }
-                px += 4;
+    /// create a mask of the sprite
+    pub fn create_mask(&self) -> SpriteMask {
+        let mut mask = SpriteMask::new(self.width as usize + 1, self.height as usize + 1);
+        for span in &self.spans.spans {
+            for x in span.x..span.x + span.len {
+                mask.set(x as usize, span.y as usize, true);
             }
-            py += 1;
-            px = x;
         }
+        mask
+    }
+
+    /// render sprite to frame with mask, only pixels set in the mask are drawn
+    pub fn render_with_mask<'a>(&self, x: u32, y: u32, mask: &SpriteMask, frame: &mut Frame<'a>) {
+        self.render_spans(x, y, &DrawStyle::NORMAL, Some(mask), frame);
     }
 
     /// render sprite to frame
-    pub fn render<'a>(&self, x: u32, y: u32, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        let mut px = x;
-        let mut py = y;
+    pub fn render<'a>(&self, x: u32, y: u32, frame: &mut Frame<'a>) {
+        self.render_spans(x, y, &DrawStyle::NORMAL, None, frame);
+    }
 
-        for sy in 0..=self.height {
-            for sx in 0..=self.width {
-                let Rgba(rgba) = sheet.texture.get_pixel(sx+self.x,sy+self.y);
-                // todo: ADD BLENDING MODE
-                if rgba[3] != 0 { // check alpha channel not 0
-                    frame.put_pixel(px, py, rgba);
-                    //else {
-                    // clip if necessary
-                    // if  px < frame.width*4 && py < frame.height*4 {
-                    //     // if rgba[0] == 0xe9 && rgba[1] == 0x1e && rgba[2] == 0x1e {
-                    //     //     println!("0xED {}", rgba[3]);
-                    //     //     // frame.frame[(px+py*frame.width*4) as usize]   = 0xFF;
-                    //     //     // frame.frame[(px+1+py*frame.width*4) as usize] = 0xFF;
-                    //     //     // frame.frame[(px+2+py*frame.width*4) as usize] = 0x0;
-                    //     //     // frame.frame[(px+3+py*frame.width*4) as usize] = 0x0;
-                    //     // }
-
-                    //     frame.frame[(px+py*frame.width*4) as usize]   = rgba[0];
-                    //     frame.frame[(px+1+py*frame.width*4) as usize] = rgba[1];
-                    //     frame.frame[(px+2+py*frame.width*4) as usize] = rgba[2];
-                    //     frame.frame[(px+3+py*frame.width*4) as usize] = rgba[3];
-                    // } 
-                    //}
+    /// render sprite to frame, blended, tinted and faded as given by a style
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x position in frame, in bytes, i.e. 4 per pixel
+    /// * `y` - y position in frame
+    /// * `style` - How sprite is drawn
+    /// * `frame` - Frame to draw to
+    pub fn render_with_style<'a>(&self, x: u32, y: u32, style: &DrawStyle, frame: &mut Frame<'a>) {
+        self.render_spans(x, y, style, None, frame);
+    }
+
+    /// render the sprite's spans to frame, each clipped to the frame once, copying runs of opaque pixels 
+    /// directly when they need no blending
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x position in frame, in bytes, i.e. 4 per pixel
+    /// * `y` - y position in frame
+    /// * `style` - How sprite is drawn
+    /// * `mask` - If given, only pixels set in the mask are drawn
+    /// * `frame` - Frame to draw to
+    fn render_spans<'a>(&self, x: u32, y: u32, style: &DrawStyle, mask: Option<&SpriteMask>, frame: &mut Frame<'a>) {
+        let copy = *style == DrawStyle::NORMAL;
+        for span in &self.spans.spans {
+            let row = match frame.row_mut(y + span.y) {
+                Some(row) => row,
+                None => continue,
+            };
+
+            // clip span to the right edge of the frame
+            let bx = (x + span.x * 4) as usize;
+            if bx >= row.len() {
+                continue;
+            }
+            let len = (span.len as usize).min((row.len() - bx) / 4);
+            let pixels = &self.spans.pixels[span.start..span.start + len * 4];
+            let row = &mut row[bx..bx + len * 4];
+
+            if copy && span.opaque && mask.is_none() {
+                row.copy_from_slice(pixels);
+                continue;
+            }
+
+            for (i, (dst, src)) in row.chunks_exact_mut(4).zip(pixels.chunks_exact(4)).enumerate() {
+                if mask.is_none_or(|mask| mask.get(span.x as usize + i, span.y as usize)) {
+                    style.draw([src[0], src[1], src[2], src[3]], dst);
                 }
-                px += 4;
             }
-            py += 1;
-            px = x;
         }
     }
 }
@@ -217,7 +584,7 @@ impl Sprite {
 // Sprite sheets
 //------------------------------------------------------------------------------
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SpriteSheet {
     pub texture: ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>,
     pub width: u32,
@@ -225,17 +592,20 @@ pub struct SpriteSheet {
 }
 
 impl SpriteSheet {
-    pub fn new<P>(image_file: P) -> SpriteSheet
+    pub fn new<P>(image_file: P) -> Result<SpriteSheet, AssetError>
         where P: AsRef<Path> {
 
-            let img : DynamicImage = image::open(image_file).unwrap();
+            let data = load_asset(&image_file)?;
+            let img : DynamicImage = image::load_from_memory(&data).map_err(|error| AssetError::Invalid { 
+                path: asset_path(&image_file).display().to_string(), 
+                error: error.to_string() })?;
             let tex = img.to_rgba8();
             let dim = tex.dimensions();
-            SpriteSheet {
+            Ok(SpriteSheet {
                 texture: tex,
                 width: dim.0,
                 height: dim.1,
-            }
+            })
     }
 }
 
@@ -243,18 +613,212 @@ impl SpriteSheet {
 // Animation JSON
 //------------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AnimationJSON {
-    pub name: String,
-    pub bullet: String,
-    pub framerate: i32,
-    pub frames: Vec<String>,
-}
-
 impl AnimationJSON {
-    pub fn from_json<P>(jsonfile: P) -> HashMap<String, AnimationJSON>
+    pub fn from_json<P>(jsonfile: P) -> Result<HashMap<String, AnimationJSON>, AssetError>
         where P: AsRef<Path> {
-            let file = File::open(jsonfile).expect("Unable open sprite sheet JSON file");
-            serde_json::from_reader(file).expect("Invalid sprite sheet JSON file")
+            read_json(jsonfile)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// returns a mask with the given pixels set
+    fn mask(width: usize, height: usize, pixels: &[(usize, usize)]) -> SpriteMask {
+        let mut mask = SpriteMask::new(width, height);
+        for (x, y) in pixels {
+            mask.set(*x, *y, true);
+        }
+        mask
+    }
+
+    #[test]
+    fn mask_overlaps_at_negative_offsets() {
+        let barrier = mask(100, 4, &[(0, 1)]);
+        let bullet = mask(8, 2, &[(5, 0)]);
+
+        assert!(barrier.overlaps(&bullet, -5, 1));
+        assert!(!barrier.overlaps(&bullet, -4, 1));
+        assert!(!barrier.overlaps(&bullet, -5, 0));
+        assert!(barrier.overlaps(&mask(8, 2, &[(5, 1)]), -5, 0));
+
+        // entirely outside of the mask
+        assert!(!barrier.overlaps(&bullet, -8, 1));
+        assert!(!barrier.overlaps(&bullet, 100, 1));
+        assert!(!barrier.overlaps(&bullet, -5, -2));
+    }
+
+    #[test]
+    fn mask_overlaps_across_words() {
+        let barrier = mask(100, 4, &[(63, 2), (64, 2)]);
+        let bullet = mask(4, 1, &[(1, 0), (2, 0)]);
+
+        assert!(barrier.overlaps(&bullet, 62, 2));
+        assert!(barrier.overlaps(&bullet, 61, 2));
+        assert!(barrier.overlaps(&bullet, 63, 2));
+        assert!(!barrier.overlaps(&bullet, 59, 2));
+        assert!(!barrier.overlaps(&bullet, 64, 2));
+        assert!(!barrier.overlaps(&bullet, 62, 1));
+    }
+
+    #[test]
+    fn mask_clear_masked_stamps_hole() {
+        let mut barrier = mask(100, 4, &[(62, 2), (63, 2), (64, 2), (65, 2), (0, 0), (1, 0)]);
+
+        // straddling the first and second words of a row
+        barrier.clear_masked(&mask(4, 1, &[(1, 0), (2, 0)]), 62, 2);
+        assert!(barrier.get(62, 2));
+        assert!(!barrier.get(63, 2));
+        assert!(!barrier.get(64, 2));
+        assert!(barrier.get(65, 2));
+
+        // partly off the left and top of the mask
+        barrier.clear_masked(&mask(3, 2, &[(2, 1)]), -2, -1);
+        assert!(!barrier.get(0, 0));
+        assert!(barrier.get(1, 0));
+        assert!(barrier.get(62, 2));
+    }
+
+    #[test]
+    fn mask_first_in_row() {
+        let barrier = mask(100, 4, &[(70, 3), (5, 1)]);
+
+        assert_eq!(barrier.first_in_row(3, 0..100), Some(70));
+        assert_eq!(barrier.first_in_row(3, 65..71), Some(70));
+        assert_eq!(barrier.first_in_row(3, 0..70), None);
+        assert_eq!(barrier.first_in_row(3, 71..200), None);
+        assert_eq!(barrier.first_in_row(1, 0..100), Some(5));
+        assert_eq!(barrier.first_in_row(1, 6..100), None);
+        assert_eq!(barrier.first_in_row(0, 0..100), None);
+    }
+
+    #[test]
+    fn blend_modes() {
+        let src = [100, 100, 100, 0xFF];
+        let dst = [200, 50, 0, 0xFF];
+
+        assert_eq!(BlendMode::Normal.blend(&src, &dst), src);
+        assert_eq!(BlendMode::Add.blend(&src, &dst), [255, 150, 100, 0xFF]);
+        assert_eq!(BlendMode::Substract.blend(&src, &dst), [0, 50, 100, 0xFF]);
+        assert_eq!(BlendMode::RevSubstract.blend(&src, &dst), [100, 0, 0, 0xFF]);
+        assert_eq!(BlendMode::Min.blend(&src, &dst), [100, 50, 0, 0xFF]);
+        assert_eq!(BlendMode::Max.blend(&src, &dst), [200, 100, 100, 0xFF]);
+    }
+
+    #[test]
+    fn blend_weighted_by_alpha() {
+        let dst = [0, 0, 0, 0];
+
+        // fully transparent leaves what is drawn
+        assert_eq!(BlendMode::Normal.blend(&[255, 255, 255, 0], &[10, 20, 30, 40]), [10, 20, 30, 40]);
+        assert_eq!(BlendMode::Add.blend(&[255, 255, 255, 0], &[10, 20, 30, 40]), [10, 20, 30, 40]);
+
+        // a fifth of the way from what is drawn to the sprite
+        assert_eq!(BlendMode::Normal.blend(&[255, 0, 0, 51], &dst), [51, 0, 0, 51]);
+        assert_eq!(BlendMode::Normal.blend(&[0, 0, 0, 51], &[255, 255, 255, 255]), [204, 204, 204, 255]);
+    }
+
+    /// size, and position, of a trimmed frame within its 4x4 sprite
+    const TRIMMED: (u32, u32) = (3, 2);
+    const TRIMMED_OFFSET: (u32, u32) = (1, 2);
+
+    /// returns a pixel of the trimmed frame, each is different
+    fn trimmed_pixel(tx: u32, ty: u32) -> [u8; 4] {
+        [(ty * TRIMMED.0 + tx) as u8 * 10 + 10, 0x80, 0x40, 0xFF]
+    }
+
+    /// returns an 8x8 sheet with the trimmed frame at (0,0), and rotated clockwise at (4,0)
+    fn sheet() -> SpriteSheet {
+        let mut texture = ImageBuffer::new(8, 8);
+        for ty in 0..TRIMMED.1 {
+            for tx in 0..TRIMMED.0 {
+                texture.put_pixel(tx, ty, image::Rgba(trimmed_pixel(tx, ty)));
+                texture.put_pixel(4 + TRIMMED.1 - 1 - ty, tx, image::Rgba(trimmed_pixel(tx, ty)));
+            }
+        }
+        SpriteSheet { texture, width: 8, height: 8 }
+    }
+
+    /// returns the trimmed sprite's frame, at a position within the sheet
+    fn sprite_json(x: i32, rotated: bool) -> SpriteJSON {
+        SpriteJSON {
+            frame: XYWHJSON { x, y: 0, w: TRIMMED.0 as i32, h: TRIMMED.1 as i32 },
+            rotated,
+            trimmed: true,
+            spriteSourceSize: XYWHJSON {
+                x: TRIMMED_OFFSET.0 as i32,
+                y: TRIMMED_OFFSET.1 as i32,
+                w: TRIMMED.0 as i32,
+                h: TRIMMED.1 as i32,
+            },
+            sourceSize: WHJSON { w: 4, h: 4 },
+        }
+    }
+
+    #[test]
+    fn trimmed_and_rotated_pixels() {
+        let sheet = sheet();
+        for rotated in [false, true] {
+            let sprite = Sprite::from_json(&sprite_json(if rotated { 4 } else { 0 }, rotated), &sheet);
+            assert_eq!((sprite.width, sprite.height), (4, 4));
+
+            for sy in 0..4 {
+                for sx in 0..4 {
+                    let (tx, ty) = (sx as i32 - TRIMMED_OFFSET.0 as i32, sy as i32 - TRIMMED_OFFSET.1 as i32);
+                    let expected = if tx >= 0 && ty >= 0 && tx < TRIMMED.0 as i32 && ty < TRIMMED.1 as i32 {
+                        Some(trimmed_pixel(tx as u32, ty as u32))
+                    }
+                    else {
+                        None
+                    };
+                    assert_eq!(sprite.pixel(sx, sy, &sheet).copied(), expected, "({}, {}) rotated {}", sx, sy, rotated);
+                }
+            }
+
+            // one past the sprite's edge repeats its last column and row
+            assert_eq!(sprite.pixel(4, 3, &sheet), sprite.pixel(3, 3, &sheet));
+            assert_eq!(sprite.pixel(3, 4, &sheet), sprite.pixel(3, 3, &sheet));
+        }
+    }
+
+    #[test]
+    fn spans_render_as_each_pixel_would() {
+        const WIDTH: u32 = 8;
+        const HEIGHT: u32 = 8;
+        const BACKGROUND: [u8; 4] = [0x20, 0x40, 0x60, 0xFF];
+
+        // a hole, and a partly transparent pixel, within the trimmed frame
+        let mut sheet = sheet();
+        sheet.texture.put_pixel(1, 0, image::Rgba([0; 4]));
+        sheet.texture.put_pixel(2, 1, image::Rgba([0xFF, 0, 0, 0x80]));
+        let sprite = Sprite::from_json(&sprite_json(0, false), &sheet);
+        let mut mask = sprite.create_mask();
+        assert!(!mask.get(2, 2));
+        mask.set(3, 3, false);
+
+        let styles = [DrawStyle::NORMAL, DrawStyle::new(BlendMode::Add, [0xFF, 0x80, 0xFF, 0xFF], 0.5)];
+        for style in styles.iter() {
+            for mask in [None, Some(&mask)] {
+                // in the frame, and clipped by its right and bottom edges
+                for (x, y) in [(0, 0), ((WIDTH - 2) * 4, HEIGHT - 3)] {
+                    let mut spans = BACKGROUND.repeat((WIDTH * HEIGHT) as usize);
+                    let mut frame = Frame::new(&mut spans, WIDTH, HEIGHT);
+                    sprite.render_spans(x, y, style, mask, &mut frame);
+
+                    let mut pixels = BACKGROUND.repeat((WIDTH * HEIGHT) as usize);
+                    let mut frame = Frame::new(&mut pixels, WIDTH, HEIGHT);
+                    for sy in 0..=sprite.height {
+                        for sx in 0..=sprite.width {
+                            let drawn = mask.is_none_or(|mask| mask.get(sx as usize, sy as usize));
+                            if let Some(rgba) = sprite.pixel(sx, sy, &sheet).filter(|rgba| rgba[3] != 0 && drawn) {
+                                style.put_pixel(x + sx * 4, y + sy, rgba, &mut frame);
+                            }
+                        }
+                    }
+
+                    assert_eq!(spans, pixels, "{:?} mask {} at ({}, {})", style, mask.is_some(), x, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Description: 
+//! 
+//! JSON representation of sprite sheets, in the format written by TexturePacker and si-pack, and 
+//! of animations. Kept apart from the sprite sheet itself, so that the asset tools can share it.
+//! 
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::collections::HashMap;
+
+//------------------------------------------------------------------------------
+// Sprite sheet JSON representation
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct XYWHJSON {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WHJSON {
+    pub w: i32,
+    pub h: i32,
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpriteJSON {
+    pub frame: XYWHJSON,
+    pub rotated: bool,
+    pub trimmed: bool,
+    pub spriteSourceSize: XYWHJSON,
+    pub sourceSize: WHJSON,
+}
+
+impl SpriteJSON {
+    /// returns the width and height of the frame within the sheet, swapped if it is rotated
+    pub fn sheet_size(&self) -> (i32, i32) {
+        if self.rotated {
+            (self.frame.h, self.frame.w)
+        }
+        else {
+            (self.frame.w, self.frame.h)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetaJSON {
+    pub app: String,
+    pub version: String,
+    pub image: String,
+    pub format: String,
+    pub size: WHJSON,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SheetJSON {
+    pub frames: HashMap<String, SpriteJSON>,
+    pub meta: MetaJSON,
+}
+
+//------------------------------------------------------------------------------
+// Animation JSON
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnimationJSON {
+    pub name: String,
+    pub bullet: String,
+    pub framerate: i32,
+    pub frames: Vec<String>,
+}
@@ -0,0 +1,281 @@
+//! Description:
+//!
+//! Grid of the alien swarm. Each alien has a cell in the grid, laid out from the swarm's top left position
+//! by the spacing between aliens, so that the aliens near a position, the lowest live alien in a column, and
+//! the left and right most columns with live aliens, are found without walking every alien. Nothing here
+//! depends on the size of the swarm, so formations can be larger than the original's 11x5.
+//!
+//! Cells are indexed in row order from the top left, each holding the handle of its alien.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::ops::Range;
+
+use crate::math::*;
+use crate::entity::Alien;
+use crate::entity_store::Handle;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwarmGrid {
+    /// number of columns in swarm
+    columns: usize,
+    /// number of rows in swarm
+    rows: usize,
+    /// top left position of swarm, x is in bytes, i.e. 4 per pixel
+    origin: Point,
+    /// distance between cells, width is in bytes
+    spacing: Size,
+    /// alien in each cell, which stays in the store when killed, ready for the next round
+    aliens: Vec<Handle<Alien>>,
+    /// is alien in each cell alive, i.e. part of the swarm
+    alive: Vec<bool>,
+    /// number of live aliens in each column
+    column_counts: Vec<u32>,
+    /// row of lowest live alien in each column, if any
+    lowest: Vec<Option<usize>>,
+}
+
+impl SwarmGrid {
+    /// create a grid with every alien alive
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Number of columns in swarm
+    /// * `rows` - Number of rows in swarm
+    /// * `origin` - Top left position of swarm, x is in bytes
+    /// * `spacing` - Distance between aliens, width is in bytes
+    /// * `aliens` - Alien in each cell, in row order
+    pub fn new(columns: usize, rows: usize, origin: Point, spacing: Size, aliens: Vec<Handle<Alien>>) -> Self {
+        assert_eq!(aliens.len(), columns * rows, "swarm needs an alien for each cell");
+        let mut grid = SwarmGrid {
+            columns,
+            rows,
+            origin,
+            spacing,
+            aliens,
+            alive: vec![],
+            column_counts: vec![],
+            lowest: vec![],
+        };
+        grid.reset(origin);
+        grid
+    }
+
+    /// bring every alien back to life, with the swarm at a new position
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - Top left position of swarm, x is in bytes
+    pub fn reset(&mut self, origin: Point) {
+        self.origin = origin;
+        self.alive = vec![true; self.columns * self.rows];
+        self.column_counts = vec![self.rows as u32; self.columns];
+        self.lowest = vec![self.rows.checked_sub(1); self.columns];
+    }
+
+    /// returns the number of columns in swarm
+    #[inline]
+    pub fn get_number_columns(&self) -> usize {
+        self.columns
+    }
+
+    /// returns the number of rows in swarm
+    #[inline]
+    pub fn get_number_rows(&self) -> usize {
+        self.rows
+    }
+
+    /// returns a mutable reference to top left position of swarm
+    #[inline]
+    pub fn get_mut_origin(&mut self) -> &mut Point {
+        &mut self.origin
+    }
+
+    /// returns the number of cells in swarm
+    #[inline]
+    pub fn get_number_cells(&self) -> usize {
+        self.aliens.len()
+    }
+
+    /// returns the alien in a cell
+    #[inline]
+    pub fn get_alien(&self, cell: usize) -> Handle<Alien> {
+        self.aliens[cell]
+    }
+
+    /// returns the cell for a given column and row
+    #[inline]
+    pub fn cell(&self, column: usize, row: usize) -> usize {
+        row * self.columns + column
+    }
+
+    /// returns the column of a cell
+    #[inline]
+    pub fn column(&self, cell: usize) -> usize {
+        cell % self.columns
+    }
+
+    /// returns the row of a cell, 0 being the top row
+    #[inline]
+    pub fn row(&self, cell: usize) -> usize {
+        cell / self.columns
+    }
+
+    /// returns the x position of a column's cells, in bytes
+    #[inline]
+    pub fn column_x(&self, column: usize) -> u32 {
+        self.origin.x + column as u32 * self.spacing.width
+    }
+
+    /// returns the top left position of a cell, x is in bytes
+    #[inline]
+    pub fn cell_position(&self, cell: usize) -> Point {
+        Point::new(self.column_x(self.column(cell)), self.origin.y + self.row(cell) as u32 * self.spacing.height)
+    }
+
+    /// returns true if the alien in a cell is alive
+    #[inline]
+    pub fn is_alive(&self, cell: usize) -> bool {
+        self.alive[cell]
+    }
+
+    /// remove an alien from the swarm, e.g. when it is hit
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - Alien's cell
+    pub fn kill(&mut self, cell: usize) {
+        if !self.alive[cell] {
+            return;
+        }
+        self.alive[cell] = false;
+
+        let column = self.column(cell);
+        self.column_counts[column] -= 1;
+        if self.lowest[column] == Some(self.row(cell)) {
+            self.lowest[column] = (0..self.row(cell)).rev().find(|row| self.alive[self.cell(column, *row)]);
+        }
+    }
+
+    /// returns the cell of the lowest live alien in a column, if any
+    #[inline]
+    pub fn lowest_in_column(&self, column: usize) -> Option<usize> {
+        self.lowest[column].map(|row| self.cell(column, row))
+    }
+
+    /// returns the columns that contain a live alien, from left to right
+    pub fn live_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.columns).filter(move |column| self.column_counts[*column] != 0)
+    }
+
+    /// returns the left most column that contains a live alien, if any
+    #[inline]
+    pub fn left_most_column(&self) -> Option<usize> {
+        self.live_columns().next()
+    }
+
+    /// returns the right most column that contains a live alien, if any
+    #[inline]
+    pub fn right_most_column(&self) -> Option<usize> {
+        self.live_columns().last()
+    }
+
+    /// returns the cells, of live aliens, that could overlap a rectangle, in row order
+    ///
+    /// The cells under the rectangle are found directly from the swarm's position. Aliens can lag a step behind
+    /// the swarm's position, when it is moved an alien at a time, so neighbouring cells are included too.
+    /// Callers still need to check the alien's own bounding box.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - Rectangle, x is in bytes
+    pub fn live_cells_near(&self, rect: &Rect) -> impl Iterator<Item = usize> + '_ {
+        let columns = Self::cells_between(
+            rect.min_x(), rect.max_x(), self.origin.x, self.spacing.width, self.columns);
+        let rows = Self::cells_between(
+            rect.min_y(), rect.max_y(), self.origin.y, self.spacing.height, self.rows);
+
+        rows.flat_map(move |row| columns.clone().map(move |column| self.cell(column, row)))
+            .filter(move |cell| self.is_alive(*cell))
+    }
+
+    /// returns the cells, along one axis, that lie between two positions, along with their neighbours
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First position
+    /// * `end` - Last position
+    /// * `origin` - Position of first cell
+    /// * `spacing` - Distance between cells
+    /// * `count` - Number of cells
+    fn cells_between(start: u32, end: u32, origin: u32, spacing: u32, count: usize) -> Range<usize> {
+        let cell = |position: u32| (position as i64 - origin as i64).div_euclid(spacing.max(1) as i64);
+        let first = (cell(start) - 1).clamp(0, count as i64) as usize;
+        let last = (cell(end) + 2).clamp(0, count as i64) as usize;
+        first..last.max(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Animation;
+    use crate::entity_store::EntityStore;
+
+    /// returns a swarm of 3 columns and 2 rows, at (100, 50), with cells 64 bytes apart and 16 rows apart
+    fn swarm() -> SwarmGrid {
+        let mut store = EntityStore::new();
+        let aliens = (0..6)
+            .map(|_| store.spawn(Alien::new(Point::new(0, 0), 10, Rect::zero(), Animation::default())))
+            .collect();
+        SwarmGrid::new(3, 2, Point::new(100, 50), Size::new(64, 16), aliens)
+    }
+
+    #[test]
+    fn kill_updates_lowest_and_columns() {
+        let mut swarm = swarm();
+        assert_eq!(swarm.lowest_in_column(1), Some(swarm.cell(1, 1)));
+
+        swarm.kill(swarm.cell(1, 1));
+        assert!(!swarm.is_alive(swarm.cell(1, 1)));
+        assert_eq!(swarm.lowest_in_column(1), Some(swarm.cell(1, 0)));
+
+        // killing a dead alien changes nothing
+        swarm.kill(swarm.cell(1, 1));
+        assert_eq!(swarm.lowest_in_column(1), Some(swarm.cell(1, 0)));
+        assert_eq!(swarm.live_columns().collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        swarm.kill(swarm.cell(1, 0));
+        assert_eq!(swarm.lowest_in_column(1), None);
+        assert_eq!(swarm.live_columns().collect::<Vec<_>>(), vec![0, 2]);
+
+        // killing above the lowest alien leaves it lowest
+        swarm.kill(swarm.cell(0, 0));
+        assert_eq!(swarm.lowest_in_column(0), Some(swarm.cell(0, 1)));
+        swarm.kill(swarm.cell(0, 1));
+        assert_eq!(swarm.left_most_column(), Some(2));
+        assert_eq!(swarm.right_most_column(), Some(2));
+
+        swarm.reset(Point::new(100, 50));
+        assert_eq!(swarm.lowest_in_column(1), Some(swarm.cell(1, 1)));
+        assert_eq!(swarm.left_most_column(), Some(0));
+    }
+
+    #[test]
+    fn live_cells_near_rect() {
+        let mut swarm = swarm();
+
+        // over the bottom right cell, including its neighbours
+        let rect = Rect::new(Point::new(228, 66), Size::new(4, 4));
+        assert_eq!(swarm.live_cells_near(&rect).collect::<Vec<_>>(), vec![1, 2, 4, 5]);
+
+        swarm.kill(swarm.cell(1, 0));
+        swarm.kill(swarm.cell(2, 1));
+        assert_eq!(swarm.live_cells_near(&rect).collect::<Vec<_>>(), vec![2, 4]);
+
+        // nowhere near the swarm
+        assert_eq!(swarm.live_cells_near(&Rect::new(Point::new(0, 0), Size::new(4, 4))).count(), 0);
+        assert_eq!(swarm.live_cells_near(&Rect::new(Point::new(1000, 60), Size::new(4, 4))).count(), 0);
+        assert_eq!(swarm.live_cells_near(&Rect::new(Point::new(228, 200), Size::new(4, 4))).count(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+//! Description:
+//!
+//! Sound effects synthesised in real time, rather than played from samples, in the spirit of
+//! the discrete analogue circuits of the original hardware. Each effect is a simple oscillator,
+//! square or noise, with an optional pitch sweep, vibrato, and decay, described by its parameters.
+//!
+//! The march is four notes, each played as the swarm steps, so that it keeps time with the swarm.
+//!
+//! Copyright © 2020 Benedict Gaster. All rights reserved.
+
+use std::time::Duration;
+
+use rodio::Source;
+
+/// sample rate of all synthesised effects
+const SYNTH_SAMPLE_RATE: u32 = 44100;
+
+/// frequencies (Hz) of the four descending march notes
+const MARCH_NOTES: [f32; 4] = [98.0, 87.3, 77.8, 73.4];
+/// how long each march note sounds, about the time between notes when the swarm is at its fastest
+const MARCH_NOTE_DURATION: Duration = Duration::from_millis(80);
+
+/// shape of oscillator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Square,
+    Noise,
+}
+
+/// parameters describing a synthesised effect
+#[derive(Debug, Clone, Copy)]
+pub struct EffectParams {
+    /// oscillator shape
+    pub waveform: Waveform,
+    /// frequency (Hz) at the start of the effect, for noise this is the rate the noise changes
+    pub start_frequency: f32,
+    /// frequency (Hz) at the end of the effect, swept linearly from the start frequency
+    pub end_frequency: f32,
+    /// depth (Hz) of vibrato
+    pub vibrato_depth: f32,
+    /// rate (Hz) of vibrato
+    pub vibrato_rate: f32,
+    /// length of effect, or None to play forever
+    pub duration: Option<Duration>,
+    /// fade out linearly over the effect's duration
+    pub decay: bool,
+    /// amplitude [0..1]
+    pub amplitude: f32,
+}
+
+/// player's shot, a quick falling sweep
+pub const SHOT: EffectParams = EffectParams {
+    waveform: Waveform::Square,
+    start_frequency: 1200.0,
+    end_frequency: 300.0,
+    vibrato_depth: 0.0,
+    vibrato_rate: 0.0,
+    duration: Some(Duration::from_millis(180)),
+    decay: true,
+    amplitude: 0.3,
+};
+
+/// player hit, a long burst of low noise
+pub const PLAYER_EXPLOSION: EffectParams = EffectParams {
+    waveform: Waveform::Noise,
+    start_frequency: 3000.0,
+    end_frequency: 600.0,
+    vibrato_depth: 0.0,
+    vibrato_rate: 0.0,
+    duration: Some(Duration::from_millis(1000)),
+    decay: true,
+    amplitude: 0.5,
+};
+
+/// alien hit, a short burst of bright noise
+pub const ALIEN_EXPLOSION: EffectParams = EffectParams {
+    waveform: Waveform::Noise,
+    start_frequency: 8000.0,
+    end_frequency: 4000.0,
+    vibrato_depth: 0.0,
+    vibrato_rate: 0.0,
+    duration: Some(Duration::from_millis(250)),
+    decay: true,
+    amplitude: 0.4,
+};
+
+/// UFO on screen, a warbling tone that plays until paused
+pub const UFO: EffectParams = EffectParams {
+    waveform: Waveform::Square,
+    start_frequency: 700.0,
+    end_frequency: 700.0,
+    vibrato_depth: 200.0,
+    vibrato_rate: 7.0,
+    duration: None,
+    decay: false,
+    amplitude: 0.2,
+};
+
+/// UFO hit, a high and fast warble that fades away
+pub const UFO_HIT: EffectParams = EffectParams {
+    waveform: Waveform::Square,
+    start_frequency: 1600.0,
+    end_frequency: 1200.0,
+    vibrato_depth: 400.0,
+    vibrato_rate: 25.0,
+    duration: Some(Duration::from_millis(800)),
+    decay: true,
+    amplitude: 0.3,
+};
+
+/// converts a duration to a number of samples
+fn samples(duration: Duration) -> u64 {
+    (duration.as_secs_f64() * SYNTH_SAMPLE_RATE as f64) as u64
+}
+
+/// a synthesised effect, playable as a rodio source
+#[derive(Debug, Clone)]
+pub struct Effect {
+    params: EffectParams,
+    /// number of samples generated so far
+    position: u64,
+    /// total number of samples, None if effect plays forever
+    length: Option<u64>,
+    /// oscillator phase [0..1]
+    phase: f32,
+    /// vibrato phase [0..1]
+    vibrato_phase: f32,
+    /// state of the noise generator, a linear feedback shift register as per the original hardware
+    noise: u32,
+    /// current noise output
+    noise_value: f32,
+}
+
+impl Effect {
+    /// create an effect, from its parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters describing the effect
+    pub fn new(params: EffectParams) -> Self {
+        Effect {
+            params,
+            position: 0,
+            length: params.duration.map(samples),
+            phase: 0.0,
+            vibrato_phase: 0.0,
+            noise: 0x1,
+            noise_value: 1.0,
+        }
+    }
+}
+
+impl Iterator for Effect {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // how far through the effect we are [0..1], effects that play forever do not sweep or decay
+        let progress = match self.length {
+            Some(length) if self.position >= length => return None,
+            Some(length) => self.position as f32 / length as f32,
+            None => 0.0,
+        };
+        self.position += 1;
+
+        let vibrato = (self.vibrato_phase * 2.0 * std::f32::consts::PI).sin() * self.params.vibrato_depth;
+        self.vibrato_phase = (self.vibrato_phase + self.params.vibrato_rate / SYNTH_SAMPLE_RATE as f32).fract();
+
+        let frequency = self.params.start_frequency +
+            (self.params.end_frequency - self.params.start_frequency) * progress + vibrato;
+        self.phase += frequency.max(0.0) / SYNTH_SAMPLE_RATE as f32;
+
+        let value = match self.params.waveform {
+            Waveform::Square => if self.phase.fract() < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Noise => {
+                // clock the shift register each time the phase wraps
+                if self.phase >= 1.0 {
+                    let bit = (self.noise ^ (self.noise >> 1)) & 1;
+                    self.noise = (self.noise >> 1) | (bit << 14);
+                    self.noise_value = if self.noise & 1 == 1 { 1.0 } else { -1.0 };
+                }
+                self.noise_value
+            },
+        };
+        self.phase = self.phase.fract();
+
+        let envelope = if self.params.decay { 1.0 - progress } else { 1.0 };
+        Some(value * envelope * self.params.amplitude)
+    }
+}
+
+impl Source for Effect {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SYNTH_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.params.duration
+    }
+}
+
+/// parameters of a single march note
+///
+/// # Arguments
+///
+/// * `note` - Index of note, cycling through the four notes
+pub fn march_note(note: usize) -> EffectParams {
+    let frequency = MARCH_NOTES[note % MARCH_NOTES.len()];
+    EffectParams {
+        waveform: Waveform::Square,
+        start_frequency: frequency,
+        end_frequency: frequency,
+        vibrato_depth: 0.0,
+        vibrato_rate: 0.0,
+        duration: Some(MARCH_NOTE_DURATION),
+        decay: true,
+        amplitude: 0.5,
+    }
+}
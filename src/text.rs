@@ -4,15 +4,18 @@
 //! bare minimum needed for Space Invaders.
 //! 
 //! Currently supports sprites for each digit and sprites for 
-//! SCORE<1>, SCORE<2>, HI-SCORE, and CREDIT.
+//! SCORE<1>, SCORE<2>, HI-SCORE, and CREDIT. There is also a tiny built in font, 
+//! which does not need the sprite sheet, used for the operator settings screen.
 //! 
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 //! 
 
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use crate::math::*;
-use crate::sprite_sheet::{SpriteSheet, SheetJSON, Sprite};
+use crate::sprite_sheet::{SpriteSheet, SheetJSON, Sprite, DrawStyle};
+use crate::asset::AssetReport;
+use crate::asset_names::*;
 use crate::frame::{Frame};
 use crate::timer::*;
 
@@ -34,32 +37,16 @@ pub struct Digits {
 }
 
 impl Digits {
-    /// initialise digits with a given spritesheet
-    pub fn new(sheet_json: &SheetJSON) -> Self {
+    /// initialise digits, decoding their sprites from a given spritesheet
+    pub fn new(sheet_json: &SheetJSON, sheet: &SpriteSheet, report: &mut AssetReport) -> Self {
 
         let mut digits = vec![];
 
         // simple add all the digits [0..9]
-        let s = sheet_json.frames.get("text_0.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_1.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_2.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_3.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_4.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_5.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_6.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_7.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_8.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
-        let s = sheet_json.frames.get("text_9.png").unwrap();
-        digits.push(Digit::new(Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32)));
+        for name in DIGIT_FRAMES.iter() {
+            let s = report.frame(sheet_json, name);
+            digits.push(Digit::new(Sprite::from_json(&s, sheet)));
+        }
 
         Digits {
             digits,
@@ -67,32 +54,33 @@ impl Digits {
     }
 
     /// render a given digit [0..9] to framebuffer
-    pub fn render<'a>(&self, digit: u32, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.digits[digit as usize % self.digits.len()].sprite.render(pos.x, pos.y, sheet, frame);
+    pub fn render<'a>(&self, digit: u32, pos: Point, frame: &mut Frame<'a>) {
+        self.digits[digit as usize % self.digits.len()].sprite.render(pos.x, pos.y, frame);
+
+    }
 
+    pub fn render_string<'a>(&self, str: String, pos: Point, frame: &mut Frame<'a>) {
+        self.render_string_with_style(str, pos, &DrawStyle::NORMAL, frame);
     }
 
-    pub fn render_string<'a>(&self, str: String, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
+    /// render a string of digits, blended, tinted and faded as given by a style, anything else is skipped
+    pub fn render_string_with_style<'a>(
+        &self, 
+        str: String, 
+        pos: Point, 
+        style: &DrawStyle, 
+        frame: &mut Frame<'a>) {
         for (i,c) in str.chars().enumerate() {
             let position = Point::new(pos.x + 60*i as u32, pos.y);
-            match c {
-                '0' => self.render(0, position, sheet, frame),
-                '1' => self.render(1, position, sheet, frame),
-                '2' => self.render(2, position, sheet, frame),
-                '3' => self.render(3, position, sheet, frame),
-                '4' => self.render(4, position, sheet, frame),
-                '5' => self.render(5, position, sheet, frame),
-                '6' => self.render(6, position, sheet, frame),
-                '7' => self.render(7, position, sheet, frame),
-                '8' => self.render(8, position, sheet, frame),
-                '9' => self.render(9, position, sheet, frame),
-                _ => { }
+            if let Some(digit) = c.to_digit(10) {
+                self.digits[digit as usize % self.digits.len()].sprite.render_with_style(
+                    position.x, position.y, style, frame);
             }
         }
     }
 
     /// render a positive power of 10 number, padded to 4 digits
-    pub fn render_num<'a>(&self, num: u32, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
+    pub fn render_num<'a>(&self, num: u32, pos: Point, frame: &mut Frame<'a>) {
         let s: String = num.to_string();
 
         let mut front_str = "".to_string();
@@ -106,23 +94,17 @@ impl Digits {
 
         front_str.push_str(&s);
 
-        self.render_string(front_str, pos, sheet, frame);
+        self.render_string(front_str, pos, frame);
     }
 }
 
-/// Text for game over animation
+/// Text for game over animation, how far through the animation is kept in `GameOverProgress`
 #[derive(Debug, Clone)]
 pub struct GameOver {
     game_over: Vec<Sprite>,
-    index: usize,
-    timer: Timer,
 }
 
 impl GameOver {
-    /// time to display each letter
-    const LETTER_DISPLAY_DURATION: Duration = Duration::from_millis(300);
-    const DISPLAY_END_DURATION: Duration = Duration::from_millis(1000);
-
     const G_INDEX: usize = 0;
     const A_INDEX: usize = 1;
     const M_INDEX: usize = 2;
@@ -134,91 +116,109 @@ impl GameOver {
     const SPACING: u32 = 50;
 
     /// create a gameover text instance
-    pub fn new(sheet_json: &SheetJSON) -> Self {
+    pub fn new(sheet_json: &SheetJSON, sheet: &SpriteSheet, report: &mut AssetReport) -> Self {
         
         let mut game_over = Vec::new();
 
-        let l = sheet_json.frames.get("letter_g.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_a.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_m.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_e.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_o.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_v.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
-        let l = sheet_json.frames.get("letter_r.png").unwrap();
-        game_over.push(Sprite::new(l.frame.x as u32, l.frame.y as u32, l.frame.w as u32, l.frame.h as u32));
+        for name in GAME_OVER_FRAMES.iter() {
+            let l = report.frame(sheet_json, name);
+            game_over.push(Sprite::from_json(&l, sheet));
+        }
 
         Self {
             game_over,
-            index: 0,
-            timer: Timer::new(Self::LETTER_DISPLAY_DURATION),
         }
     }
 
-    /// Start game over message display
-    #[inline]
-    pub fn start(&mut self) {
-        self.index = 0;
-        self.timer.reset();
-    }
-
+    /// render the letters displayed so far
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - How far through the animation it is
+    /// * `pos` - Position of text
+    /// * `frame` - Frame to render to
     #[inline]
-    pub fn next(&mut self) {
-        if self.timer.has_expired() {
-            self.index += 1;
-            if self.index > 7 {
-                self.timer.set_duration(Self::DISPLAY_END_DURATION);
-            }
-            else {
-            }
-            self.timer.reset();
-        }
-    } 
-
-    #[inline]
-    pub fn render<'a>(&self, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
+    pub fn render<'a>(&self, progress: &GameOverProgress, pos: Point, frame: &mut Frame<'a>) {
+        let index = progress.index;
         // 'g'
-        self.game_over[Self::G_INDEX].render(pos.x, pos.y, sheet, frame);
+        self.game_over[Self::G_INDEX].render(pos.x, pos.y, frame);
         // 'a'
-        if self.index > 0 {
-            self.game_over[Self::A_INDEX].render(pos.x + Self::SPACING, pos.y, sheet, frame);
+        if index > 0 {
+            self.game_over[Self::A_INDEX].render(pos.x + Self::SPACING, pos.y, frame);
         }
         // 'm'
-        if self.index > 1 {
-            self.game_over[Self::M_INDEX].render(pos.x + Self::SPACING*2, pos.y, sheet, frame);
+        if index > 1 {
+            self.game_over[Self::M_INDEX].render(pos.x + Self::SPACING*2, pos.y, frame);
         }
         // 'e'
-        if self.index > 2 {
-            self.game_over[Self::E_INDEX].render(pos.x + Self::SPACING*3, pos.y, sheet, frame);
+        if index > 2 {
+            self.game_over[Self::E_INDEX].render(pos.x + Self::SPACING*3, pos.y, frame);
         }
         // ' '
         // 'o'
-        if self.index > 4 {
-            self.game_over[Self::O_INDEX].render(pos.x + Self::SPACING*5, pos.y, sheet, frame);
+        if index > 4 {
+            self.game_over[Self::O_INDEX].render(pos.x + Self::SPACING*5, pos.y, frame);
         }
         // 'v'
-        if self.index > 5 {
-            self.game_over[Self::V_INDEX].render(pos.x + Self::SPACING*6, pos.y, sheet, frame);
+        if index > 5 {
+            self.game_over[Self::V_INDEX].render(pos.x + Self::SPACING*6, pos.y, frame);
         }
         // 'e'
-        if self.index > 6 {
-            self.game_over[Self::E_INDEX].render(pos.x + Self::SPACING*7, pos.y, sheet, frame);
+        if index > 6 {
+            self.game_over[Self::E_INDEX].render(pos.x + Self::SPACING*7, pos.y, frame);
         }
         // r'
-        if self.index > 7 {
-            self.game_over[Self::R_INDEX].render(pos.x + Self::SPACING*8, pos.y, sheet, frame);
+        if index > 7 {
+            self.game_over[Self::R_INDEX].render(pos.x + Self::SPACING*8, pos.y, frame);
+        }
+    }
+
+}
+
+/// how far through the game over animation it is, part of the game's state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameOverProgress {
+    /// letters displayed so far
+    index: usize,
+    timer: Timer,
+}
+
+impl GameOverProgress {
+    /// time to display each letter
+    const LETTER_DISPLAY_DURATION: Duration = Duration::from_millis(300);
+    const DISPLAY_END_DURATION: Duration = Duration::from_millis(1000);
+
+    pub fn new() -> Self {
+        GameOverProgress {
+            index: 0,
+            timer: Timer::new(Self::LETTER_DISPLAY_DURATION),
+        }
+    }
+
+    /// Start game over message display
+    #[inline]
+    pub fn start(&mut self, now: Duration) {
+        self.index = 0;
+        self.timer.reset(now);
+    }
+
+    #[inline]
+    pub fn next(&mut self, now: Duration) {
+        if self.timer.has_expired(now) {
+            self.index += 1;
+            if self.index > 7 {
+                self.timer.set_duration(Self::DISPLAY_END_DURATION);
+            }
+            else {
+            }
+            self.timer.reset(now);
         }
     }
 
     /// returns true if displaying game over is completed, otherwise false
     #[inline]
-    pub fn end(&self) -> bool {
-        self.index > 7 && self.timer.has_expired()
+    pub fn end(&self, now: Duration) -> bool {
+        self.index > 7 && self.timer.has_expired(now)
     }
 }
 
@@ -233,22 +233,22 @@ pub struct Score {
 }
 
 impl Score {
-    pub fn new(sheet_json: &SheetJSON) -> Self {
+    pub fn new(sheet_json: &SheetJSON, sheet: &SpriteSheet, report: &mut AssetReport) -> Self {
         
-        let s = sheet_json.frames.get("score.png").unwrap();
-        let score = Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32);
+        let s = report.frame(sheet_json, SCORE_FRAME);
+        let score = Sprite::from_json(&s, sheet);
 
-        let s = sheet_json.frames.get("score_1.png").unwrap();
-        let score_player1 = Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32);
+        let s = report.frame(sheet_json, SCORE_PLAYER1_FRAME);
+        let score_player1 = Sprite::from_json(&s, sheet);
 
-        let s = sheet_json.frames.get("score_2.png").unwrap();
-        let score_player2 = Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32);
+        let s = report.frame(sheet_json, SCORE_PLAYER2_FRAME);
+        let score_player2 = Sprite::from_json(&s, sheet);
         
-        let s = sheet_json.frames.get("hi_score.png").unwrap();
-        let hi_score = Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32);
+        let s = report.frame(sheet_json, HI_SCORE_FRAME);
+        let hi_score = Sprite::from_json(&s, sheet);
 
-        let s = sheet_json.frames.get("credit.png").unwrap();
-        let credit = Sprite::new(s.frame.x as u32, s.frame.y as u32, s.frame.w as u32, s.frame.h as u32);
+        let s = report.frame(sheet_json, CREDIT_FRAME);
+        let credit = Sprite::from_json(&s, sheet);
         
         Score {
             score,
@@ -260,24 +260,115 @@ impl Score {
     }
 
     #[inline]
-    pub fn render_player1<'a>(&self, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.score.render(pos.x, pos.y, sheet, frame);
-        self.score_player1.render(pos.x+self.score.width+260, pos.y, sheet, frame);
+    pub fn render_player1<'a>(&self, pos: Point, frame: &mut Frame<'a>) {
+        self.score.render(pos.x, pos.y, frame);
+        self.score_player1.render(pos.x+self.score.width+260, pos.y, frame);
     }
 
     #[inline]
-    pub fn render_player2<'a>(&self, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.score.render(pos.x, pos.y, sheet, frame);
-        self.score_player2.render(pos.x+self.score.width+260, pos.y, sheet, frame);
+    pub fn render_player2<'a>(&self, pos: Point, frame: &mut Frame<'a>) {
+        self.score.render(pos.x, pos.y, frame);
+        self.score_player2.render(pos.x+self.score.width+260, pos.y, frame);
     }
 
     #[inline]
-    pub fn render_hi_score<'a>(&self, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.hi_score.render(pos.x, pos.y, sheet, frame);
+    pub fn render_hi_score<'a>(&self, pos: Point, frame: &mut Frame<'a>) {
+        self.hi_score.render(pos.x, pos.y, frame);
     }
 
     #[inline]
-    pub fn render_credit<'a>(&self, pos: Point, sheet: &SpriteSheet, frame: &mut Frame<'a>) {
-        self.credit.render(pos.x, pos.y, sheet, frame);
+    pub fn render_credit<'a>(&self, pos: Point, frame: &mut Frame<'a>) {
+        self.credit.render(pos.x, pos.y, frame);
+    }
+}
+
+//------------------------------------------------------------------------------
+// Built in font
+//------------------------------------------------------------------------------
+
+/// width of a glyph in the built in font
+const SYSTEM_FONT_WIDTH: u32 = 5;
+/// height of a glyph in the built in font
+const SYSTEM_FONT_HEIGHT: u32 = 7;
+/// each font pixel is drawn as a square of this many screen pixels
+const SYSTEM_FONT_SCALE: u32 = 2;
+
+/// returns the 5x7 glyph for a character, each row is 5 bits with the left most pixel in bit 4
+fn system_font_glyph(c: char) -> [u8; SYSTEM_FONT_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        _ => [0x00; SYSTEM_FONT_HEIGHT as usize],
+    }
+}
+
+/// render text with the built in font, unknown characters are drawn as spaces
+/// 
+/// # Arguments
+/// 
+/// * `text` - text to be drawn
+/// * `pos` - top left position of text
+/// * `colour` - colour of text
+/// * `frame` - framebuffer to draw to
+pub fn render_system_text<'a>(text: &str, pos: Point, colour: [u8; 4], frame: &mut Frame<'a>) {
+    // as with sprites, x is in bytes, i.e. 4 per pixel
+    let advance = (SYSTEM_FONT_WIDTH + 1) * SYSTEM_FONT_SCALE * 4;
+    for (i, c) in text.chars().enumerate() {
+        let glyph = system_font_glyph(c);
+        let x = pos.x + advance * i as u32;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..SYSTEM_FONT_WIDTH {
+                if bits & (0x10 >> col) != 0 {
+                    for sy in 0..SYSTEM_FONT_SCALE {
+                        for sx in 0..SYSTEM_FONT_SCALE {
+                            frame.put_pixel(
+                                x + (col * SYSTEM_FONT_SCALE + sx) * 4,
+                                pos.y + row as u32 * SYSTEM_FONT_SCALE + sy,
+                                &colour);
+                        }
+                    }
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
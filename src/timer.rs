@@ -2,37 +2,59 @@
 //! 
 //! Really (really) basic timer. Designed for polling synchronosuly.
 //! 
+//! Timers are measured against the game's clock, i.e. how long the game has been running, rather than the
+//! wall clock, so that they can be saved and restored along with the rest of the game's state.
+//!
 //! Copyright © 2020 Benedict Gaster. All rights reserved.
 
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Timer {
     /// duration that time is to run for, when reset
     duration: Duration,
-    /// time since last reset
-    start: Instant,
+    /// game time of last reset
+    start: Duration,
 }
 
 impl Timer {
-    /// create a timer that last for duration
+    /// create a timer that last for duration, from the start of the game
     pub fn new(duration: Duration) -> Self {
         Timer {
             duration,
-            // reset timer to now
-            start: Instant::now(),
+            start: Duration::ZERO,
         }
     }
 
     /// reset time from now
-    pub fn reset(&mut self) {
-        self.start = Instant::now()
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current game time
+    pub fn reset(&mut self, now: Duration) {
+        self.start = now
     }
 
     /// check if time since last reset is great than timer duration, 
     /// return true if the case, otherwise false. will continue to return true, until reset
-    pub fn has_expired(&self) -> bool {
-        Instant::now() - self.start >= self.duration 
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current game time
+    pub fn has_expired(&self, now: Duration) -> bool {
+        now.saturating_sub(self.start) >= self.duration
+    }
+
+    /// returns how far through its duration the timer is, from 0 when reset to 1 once expired
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current game time
+    pub fn progress(&self, now: Duration) -> f32 {
+        if self.duration.as_secs_f32() == 0.0 {
+            return 1.0;
+        }
+        (now.saturating_sub(self.start).as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
     }
 
     pub fn set_duration(&mut self, duration: Duration) {